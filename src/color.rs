@@ -0,0 +1,44 @@
+use std::env;
+use std::io::{self, IsTerminal};
+use std::sync::OnceLock;
+
+pub const RED: &str = "\x1b[31m";
+pub const GREEN: &str = "\x1b[32m";
+pub const CYAN: &str = "\x1b[36m";
+pub const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+// Decide once whether output is colored, before anything is printed
+pub fn init(no_color: bool) {
+    let _ = ENABLED.set(policy(no_color));
+}
+
+pub fn enabled() -> bool {
+    *ENABLED.get_or_init(|| policy(false))
+}
+
+// --no-color and NO_COLOR turn colors off, CLICOLOR_FORCE turns them on,
+// CLICOLOR=0 turns them off, and otherwise only a terminal gets them
+fn policy(no_color: bool) -> bool {
+    let value = |name: &str| env::var_os(name).filter(|value| !value.is_empty());
+    if no_color || value("NO_COLOR").is_some() {
+        false
+    } else if value("CLICOLOR_FORCE").is_some_and(|value| value != "0") {
+        true
+    } else if value("CLICOLOR").is_some_and(|value| value == "0") {
+        false
+    } else {
+        io::stdout().is_terminal()
+    }
+}
+
+// `text` wrapped in `style` when colors are enabled
+pub fn paint(text: &str, style: &str) -> String {
+    if enabled() && !style.is_empty() {
+        format!("{}{}{}", style, text, RESET)
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,86 @@
+use git2::{BranchType, Repository};
+use serde::Serialize;
+use std::cmp::Reverse;
+
+use crate::config::{load_config, ConfigArgs};
+use crate::metadata::{metadata_file, parse_branch, read_metadata};
+
+#[derive(Debug, Serialize)]
+struct BranchInfo {
+    name: String,
+    current: bool,
+    #[serde(rename = "type")]
+    type_: String,
+    scope: String,
+    subject: String,
+    // Tip commit time, seconds since the epoch
+    last_commit: i64,
+}
+
+// List the feature branches gwf knows about with their stored subjects
+pub fn branches(sort_by_date: bool, json: bool, config_args: &ConfigArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = Repository::open_from_env()?;
+    let config = load_config(&repo, config_args)?;
+
+    let mut infos = Vec::new();
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        let Some(name) = branch.name()? else { continue };
+        let metadata = if config.uses_metadata() {
+            read_metadata(&metadata_file(&repo, &config, name)?)?
+        } else {
+            None
+        };
+        let parsed = parse_branch(name);
+        if metadata.is_none() && parsed.is_none() {
+            continue;
+        }
+
+        let metadata = metadata.unwrap_or_default();
+        let type_ = metadata.type_.as_deref().or(parsed.as_ref().map(|b| b.type_)).unwrap_or_default();
+        let scope = metadata.scope.as_deref().or(parsed.as_ref().map(|b| b.scope)).unwrap_or_default();
+        let subject = match metadata.message.lines().next() {
+            Some(subject) => subject.to_string(),
+            None => parsed.as_ref().map(|b| b.message.replace('-', " ")).unwrap_or_default(),
+        };
+        infos.push(BranchInfo {
+            name: name.to_string(),
+            current: branch.is_head(),
+            type_: type_.to_string(),
+            scope: scope.to_string(),
+            subject,
+            last_commit: branch.get().peel_to_commit()?.time().seconds(),
+        });
+    }
+
+    if sort_by_date {
+        infos.sort_by_key(|info| Reverse(info.last_commit));
+    } else {
+        infos.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&infos)?);
+        return Ok(());
+    }
+    if infos.is_empty() {
+        println!("No feature branches found");
+        return Ok(());
+    }
+
+    let width = |column: fn(&BranchInfo) -> &str, title: &str| {
+        infos.iter().map(|info| column(info).chars().count()).max().unwrap_or(0).max(title.len())
+    };
+    let name_width = width(|info| &info.name, "BRANCH");
+    let type_width = width(|info| &info.type_, "TYPE");
+    let scope_width = width(|info| &info.scope, "SCOPE");
+    println!("  {:name_width$}  {:type_width$}  {:scope_width$}  SUBJECT", "BRANCH", "TYPE", "SCOPE");
+    for info in &infos {
+        let marker = if info.current { "*" } else { " " };
+        println!(
+            "{} {:name_width$}  {:type_width$}  {:scope_width$}  {}",
+            marker, info.name, info.type_, info.scope, info.subject
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use git2::{Oid, Repository};
+use std::collections::HashMap;
+use std::fs;
+
+use crate::conventional::parse_commit_message;
+use crate::history::{commits, warn_if_shallow};
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    fn name(self) -> &'static str {
+        match self {
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        }
+    }
+}
+
+// Suggest the next semver version from the conventional commits since the last tag
+pub fn bump(apply: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = Repository::open_from_env()?;
+    let head = repo.head()?.peel_to_commit()?;
+
+    // Map tagged commits to their tag names
+    let mut tags: HashMap<Oid, String> = HashMap::new();
+    for name in repo.tag_names(None)?.iter().flatten() {
+        if let Ok(commit) = repo.revparse_single(&format!("refs/tags/{}", name)).and_then(|o| o.peel_to_commit()) {
+            tags.insert(commit.id(), name.to_string());
+        }
+    }
+
+    // Walk back from HEAD until the most recent tagged commit
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(head.id())?;
+    let mut last_tag = None;
+    let mut level = None;
+    for commit in commits(&repo, revwalk) {
+        let commit = commit?;
+        if let Some(tag) = tags.get(&commit.id()) {
+            last_tag = Some(tag.clone());
+            break;
+        }
+        let commit_level = commit_bump(&String::from_utf8_lossy(commit.message_bytes()));
+        if commit_level > level {
+            level = commit_level;
+        }
+    }
+
+    // Without a tag the walk went all the way back, which a shallow clone cuts short
+    if last_tag.is_none() {
+        warn_if_shallow(&repo, "bump");
+    }
+    let since = last_tag.as_deref().unwrap_or("the first commit");
+    let Some(level) = level else {
+        println!("No feat, fix or breaking commits since {}, nothing to release", since);
+        return Ok(());
+    };
+
+    let current = current_version(&repo, last_tag.as_deref())?;
+    let (major, minor, patch) = parse_version(&current)
+        .ok_or_else(|| format!("Could not parse current version '{}'", current))?;
+    let next = match level {
+        Bump::Major => format!("{}.0.0", major + 1),
+        Bump::Minor => format!("{}.{}.0", major, minor + 1),
+        Bump::Patch => format!("{}.{}.{}", major, minor, patch + 1),
+    };
+
+    println!("Commits since {} call for a {} bump: {} -> {}", since, level.name(), current, next);
+
+    if apply {
+        // Follow the prefix convention of the previous tag
+        let prefix = match &last_tag {
+            Some(tag) if !tag.starts_with('v') => "",
+            _ => "v",
+        };
+        let tag_name = format!("{}{}", prefix, next);
+        if repo.find_reference(&format!("refs/tags/{}", tag_name)).is_ok() {
+            return Err(format!("Tag '{}' already exists", tag_name).into());
+        }
+        repo.tag_lightweight(&tag_name, head.as_object(), false)?;
+        println!("Created tag: {}", tag_name);
+    }
+
+    Ok(())
+}
+
+fn commit_bump(message: &str) -> Option<Bump> {
+    let commit = parse_commit_message(message).ok()?;
+    if commit.breaking {
+        return Some(Bump::Major);
+    }
+    match commit.type_ {
+        "feat" => Some(Bump::Minor),
+        "fix" | "perf" => Some(Bump::Patch),
+        _ => None,
+    }
+}
+
+// Version from Cargo.toml, package.json or the last tag, in that order
+fn current_version(repo: &Repository, last_tag: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
+    if let Some(workdir) = repo.workdir() {
+        if let Ok(content) = fs::read_to_string(workdir.join("Cargo.toml")) {
+            let manifest: toml::Value = toml::from_str(&content)?;
+            if let Some(version) = manifest.get("package").and_then(|p| p.get("version")).and_then(|v| v.as_str()) {
+                return Ok(version.to_string());
+            }
+        }
+        if let Ok(content) = fs::read_to_string(workdir.join("package.json")) {
+            let manifest: serde_json::Value = serde_json::from_str(&content)?;
+            if let Some(version) = manifest.get("version").and_then(|v| v.as_str()) {
+                return Ok(version.to_string());
+            }
+        }
+    }
+    match last_tag {
+        Some(tag) => Ok(tag.trim_start_matches('v').to_string()),
+        None => Ok("0.0.0".to_string()),
+    }
+}
+
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    // Ignore pre-release and build metadata
+    let version = version.split(['-', '+']).next()?;
+    let mut parts = version.split('.').map(|part| part.parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+    Some((major, minor, patch))
+}
@@ -0,0 +1,122 @@
+use git2::Repository;
+
+use crate::config::{load_config, ConfigArgs};
+use crate::conventional::{has_breaking_footer, parse_conventional};
+use crate::date::{parse_date, parse_until};
+use crate::history::{commits, warn_if_shallow};
+
+// Which commits go into the changelog
+#[derive(Debug, Default)]
+pub struct ChangelogOptions {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub types: Vec<String>,
+    pub config_args: ConfigArgs,
+}
+
+// Print the conventional commits in the selected range, grouped by type
+pub fn changelog(options: &ChangelogOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = Repository::open_from_env()?;
+    let config = load_config(&repo, &options.config_args)?;
+
+    let since = options.since.as_deref().map(parse_date).transpose()?.map(|t| t.seconds());
+    let until = options.until.as_deref().map(parse_until).transpose()?;
+
+    let to = options.to.as_deref().unwrap_or("HEAD");
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(repo.revparse_single(to)?.peel_to_commit()?.id())?;
+    if let Some(from) = &options.from {
+        revwalk.hide(repo.revparse_single(from)?.peel_to_commit()?.id())?;
+    }
+
+    // (type, line) in history order, newest first
+    let mut entries: Vec<(String, String)> = Vec::new();
+    let mut dependencies: Vec<String> = Vec::new();
+    warn_if_shallow(&repo, "the changelog");
+    for commit in commits(&repo, revwalk) {
+        let commit = commit?;
+        let time = commit.time().seconds();
+        if since.is_some_and(|since| time < since) || until.is_some_and(|until| time >= until) {
+            continue;
+        }
+        let summary = String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()).into_owned();
+        let Ok(mut parsed) = parse_conventional(&summary) else {
+            continue;
+        };
+        parsed.breaking |= has_breaking_footer(&String::from_utf8_lossy(commit.message_bytes()));
+        if !options.types.is_empty() && !options.types.iter().any(|t| t == parsed.type_) {
+            continue;
+        }
+        let id = commit.id().to_string();
+        // Dependency updates get their own section, where the scope goes without saying
+        if let Some(deps_scope) = &config.deps_scope {
+            if parsed.scope.is_some_and(|scope| scope.eq_ignore_ascii_case(deps_scope)) {
+                let line = format!("- {}: {} ({})", parsed.type_, parsed.subject, &id[..7]);
+                dependencies.push(if parsed.breaking { format!("{} **BREAKING**", line) } else { line });
+                continue;
+            }
+        }
+        let line = match parsed.scope {
+            Some(scope) => format!("- **{}:** {} ({})", scope, parsed.subject, &id[..7]),
+            None => format!("- {} ({})", parsed.subject, &id[..7]),
+        };
+        let line = if parsed.breaking { format!("{} **BREAKING**", line) } else { line };
+        entries.push((parsed.type_.to_string(), line));
+    }
+
+    let mut range = match &options.from {
+        Some(from) => format!("{}..{}", from, to),
+        None => to.to_string(),
+    };
+    if let Some(since) = &options.since {
+        range.push_str(&format!(", since {}", since));
+    }
+    if let Some(until) = &options.until {
+        range.push_str(&format!(", until {}", until));
+    }
+    println!("# Changelog ({})", range);
+
+    if entries.is_empty() && dependencies.is_empty() {
+        println!("\nNo conventional commits in this range");
+        return Ok(());
+    }
+
+    // Configured types first, in their configured order, then any others
+    let mut types = config.type_names();
+    for (type_, _) in &entries {
+        if !types.contains(type_) {
+            types.push(type_.clone());
+        }
+    }
+    for type_ in &types {
+        let lines: Vec<&str> = entries.iter().filter(|(t, _)| t == type_).map(|(_, line)| line.as_str()).collect();
+        if lines.is_empty() {
+            continue;
+        }
+        println!("\n## {}\n", heading(type_));
+        for line in lines {
+            println!("{}", line);
+        }
+    }
+    if !dependencies.is_empty() {
+        println!("\n## Dependencies\n");
+        for line in &dependencies {
+            println!("{}", line);
+        }
+    }
+    Ok(())
+}
+
+fn heading(type_: &str) -> String {
+    match type_ {
+        "feat" => "Features".to_string(),
+        "fix" => "Bug Fixes".to_string(),
+        "perf" => "Performance".to_string(),
+        "docs" => "Documentation".to_string(),
+        "refactor" => "Refactoring".to_string(),
+        "revert" => "Reverts".to_string(),
+        _ => type_.to_string(),
+    }
+}
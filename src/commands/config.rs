@@ -0,0 +1,27 @@
+use git2::Repository;
+
+use crate::config::{load_config_with_sources, ConfigArgs};
+
+// Print the effective config as TOML, with where each setting came from when verbose
+pub fn show_config(config_args: &ConfigArgs, verbose: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = Repository::open_from_env()?;
+    let (config, sources) = load_config_with_sources(&repo, config_args)?;
+
+    if !verbose {
+        print!("{}", toml::to_string(&config)?);
+        return Ok(());
+    }
+
+    // One key at a time so each gets its source. Plain values must come
+    // before tables, or TOML would read them as part of the table
+    let table = toml::Table::try_from(&config)?;
+    let (values, tables): (Vec<_>, Vec<_>) = table.into_iter().partition(|(_, value)| !value.is_table());
+    for (key, value) in values.into_iter().chain(tables) {
+        let source = sources.get(&key).map(String::as_str).unwrap_or("default");
+        let mut single = toml::Table::new();
+        single.insert(key, value);
+        println!("# from {}", source);
+        print!("{}", toml::to_string(&single)?);
+    }
+    Ok(())
+}
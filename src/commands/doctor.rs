@@ -0,0 +1,100 @@
+use git2::Repository;
+use std::fs;
+use std::process::Command as ExternalCommand;
+
+use crate::config::{config_file, load_config, ConfigArgs};
+use crate::metadata::metadata_dir;
+
+struct Check {
+    name: &'static str,
+    result: Result<String, String>,
+    hint: &'static str,
+}
+
+// Check the environment for the usual setup problems
+pub fn doctor(config_args: &ConfigArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut checks = Vec::new();
+
+    let repo = Repository::open_from_env();
+    checks.push(Check {
+        name: "git repository",
+        result: match &repo {
+            Ok(repo) => Ok(repo.workdir().unwrap_or(repo.path()).display().to_string()),
+            Err(e) => Err(e.message().to_string()),
+        },
+        hint: "run gwf inside a git repository, or set GIT_DIR",
+    });
+
+    let git_config = match &repo {
+        Ok(repo) => repo.config(),
+        Err(_) => git2::Config::open_default(),
+    };
+    for (name, key) in [("git user.name", "user.name"), ("git user.email", "user.email")] {
+        checks.push(Check {
+            name,
+            result: git_config
+                .as_ref()
+                .map_err(|e| e.message().to_string())
+                .and_then(|config| config.get_string(key).map_err(|_| "not set".to_string())),
+            hint: "set it with `git config --global user.name/user.email`",
+        });
+    }
+
+    if let Ok(repo) = &repo {
+        let config = match config_file(repo, config_args) {
+            Ok(path) => {
+                let source = path.map_or("none, using defaults".to_string(), |p| p.display().to_string());
+                load_config(repo, config_args).map(|config| (config, source))
+            }
+            Err(e) => Err(e),
+        };
+        checks.push(Check {
+            name: "gwf config",
+            result: config.as_ref().map(|(_, source)| source.clone()).map_err(|e| e.to_string()),
+            hint: "fix the reported error in gwf.toml",
+        });
+
+        if let Ok((config, _)) = &config {
+            checks.push(Check {
+                name: "metadata directory",
+                result: metadata_dir(repo, config).map_err(|e| e.to_string()).and_then(|dir| {
+                    // Prove we can actually write there
+                    let probe = dir.join(".gwf-doctor");
+                    fs::create_dir_all(&dir)
+                        .and_then(|_| fs::write(&probe, b""))
+                        .and_then(|_| fs::remove_file(&probe))
+                        .map(|_| dir.display().to_string())
+                        .map_err(|e| format!("{} is not writable: {}", dir.display(), e))
+                }),
+                hint: "fix the directory permissions, or set GWF_DIR / metadata_location",
+            });
+        }
+    }
+
+    checks.push(Check {
+        name: "post-commit shell",
+        result: match ExternalCommand::new("sh").arg("-c").arg("true").status() {
+            Ok(status) if status.success() => Ok("sh".to_string()),
+            Ok(status) => Err(format!("sh exited with {}", status)),
+            Err(e) => Err(format!("could not run sh: {}", e)),
+        },
+        hint: "install a POSIX shell available as `sh` on PATH",
+    });
+
+    let mut failures = 0;
+    for check in &checks {
+        match &check.result {
+            Ok(detail) => println!("[ok]   {}: {}", check.name, detail),
+            Err(error) => {
+                failures += 1;
+                println!("[fail] {}: {}", check.name, error);
+                println!("       hint: {}", check.hint);
+            }
+        }
+    }
+
+    if failures > 0 {
+        return Err(format!("{} check(s) failed", failures).into());
+    }
+    Ok(())
+}
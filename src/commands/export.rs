@@ -0,0 +1,74 @@
+use chrono::{DateTime, FixedOffset};
+use git2::{Repository, Time};
+use serde::Serialize;
+use std::io::{self, Write};
+
+use crate::conventional::{has_breaking_footer, parse_conventional};
+use crate::history::{commits, warn_if_shallow};
+
+#[derive(Debug, Serialize)]
+struct ExportedCommit<'a> {
+    sha: String,
+    #[serde(rename = "type")]
+    type_: &'a str,
+    scope: Option<&'a str>,
+    subject: &'a str,
+    breaking: bool,
+    author: String,
+    date: String,
+}
+
+// Print the conventional commits in a range as a JSON array, writing each one
+// as the walk reaches it so long histories don't pile up in memory
+pub fn export(from: Option<&str>, to: Option<&str>, limit: Option<usize>) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = Repository::open_from_env()?;
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(repo.revparse_single(to.unwrap_or("HEAD"))?.peel_to_commit()?.id())?;
+    if let Some(from) = from {
+        revwalk.hide(repo.revparse_single(from)?.peel_to_commit()?.id())?;
+    }
+    warn_if_shallow(&repo, "export");
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    write!(out, "[")?;
+    let mut exported = 0;
+    for commit in commits(&repo, revwalk) {
+        if limit.is_some_and(|limit| exported >= limit) {
+            break;
+        }
+        let commit = commit?;
+        let summary = String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()).into_owned();
+        let Ok(mut parsed) = parse_conventional(&summary) else {
+            continue;
+        };
+        parsed.breaking |= has_breaking_footer(&String::from_utf8_lossy(commit.message_bytes()));
+        let author = commit.author();
+        let entry = ExportedCommit {
+            sha: commit.id().to_string(),
+            type_: parsed.type_,
+            scope: parsed.scope,
+            subject: parsed.subject,
+            breaking: parsed.breaking,
+            author: format!(
+                "{} <{}>",
+                String::from_utf8_lossy(author.name_bytes()),
+                String::from_utf8_lossy(author.email_bytes())
+            ),
+            date: rfc3339(commit.time()),
+        };
+        let separator = if exported == 0 { "\n  " } else { ",\n  " };
+        write!(out, "{}{}", separator, serde_json::to_string(&entry)?)?;
+        exported += 1;
+    }
+    writeln!(out, "{}]", if exported == 0 { "" } else { "\n" })?;
+    Ok(())
+}
+
+// A git timestamp in its own offset, e.g. 2024-05-01T12:30:00+02:00
+fn rfc3339(time: Time) -> String {
+    let offset = FixedOffset::east_opt(time.offset_minutes() * 60).unwrap_or(FixedOffset::east_opt(0).unwrap());
+    DateTime::from_timestamp(time.seconds(), 0)
+        .map(|date| date.with_timezone(&offset).to_rfc3339())
+        .unwrap_or_default()
+}
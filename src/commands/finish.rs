@@ -1,102 +1,1253 @@
-use git2::Repository;
+use dialoguer::{Editor, Input, MultiSelect};
+use git2::build::TreeUpdateBuilder;
+use git2::{BranchType, Commit, DiffOptions, ErrorCode, FileMode, Index, ObjectType, Oid, Repository, ResetType, Signature, StashFlags};
+use regex::Regex;
+use std::env;
 use std::fs;
-use std::path::PathBuf;
-use std::process::Command as ExternalCommand;
-use slug::slugify;
-use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::io::{self, Write};
+use std::process::{Command as ExternalCommand, Stdio};
 
-const GWF_DIR: &str = ".gwf";
-const GWF_CONFIG: &str = "gwf.toml";
+use crate::commands::nfb::create_and_checkout;
+use crate::config::{load_config, Config, ConfigArgs};
+use crate::conventional::parse_conventional;
+use crate::date::parse_date;
+use crate::diff::print_diff;
+use crate::error::IoContext;
+use crate::history::{commits, warn_if_shallow};
+use crate::i18n::t;
+use crate::message::{
+    encode_message, fill_placeholders, long_body_lines, lossy_utf8, normalize_message, render_template, split_subject,
+    strip_comments, wrap_body, EncodedMessage, Trailers,
+};
+use crate::metadata::{metadata_file, parse_branch, read_metadata, BranchMetadata};
+use crate::prompt::{can_prompt, confirm};
+use crate::signature::{signature, Role};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Config {
-    post_commit_command: String,
-}
+const DEFAULT_TICKET_FOOTER: &str = "Refs";
+const PRE_COMMIT_CONFIG: &str = ".pre-commit-config.yaml";
+const DEFAULT_HEADER_TEMPLATE: &str = "{type}{(scope)}: {subject}";
+// Header of the first commit in a new repository when none was given
+const INITIAL_COMMIT_TYPE: &str = "chore";
+const INITIAL_COMMIT_MESSAGE: &str = "initial commit";
 
-fn get_gwf_dir() -> PathBuf {
-    dirs::home_dir().unwrap().join(GWF_DIR)
+// Overrides for the values finish would otherwise derive from the branch
+#[derive(Debug, Default)]
+pub struct FinishOptions {
+    pub force: bool,
+    pub type_: Option<String>,
+    pub scope: Option<String>,
+    pub message: Option<String>,
+    pub onto: Option<String>,
+    pub config_args: ConfigArgs,
+    pub show_diff: bool,
+    pub dry_run: bool,
+    pub reword: bool,
+    pub tag: Option<String>,
+    pub annotated: bool,
+    pub tag_message: Option<String>,
+    pub squash: bool,
+    pub base: Option<String>,
+    pub stash: bool,
+    pub edit: bool,
+    pub quiet: bool,
+    pub ignore_post_commit_failure: bool,
+    pub raw_output: bool,
+    pub date: Option<String>,
+    pub author_date: Option<String>,
+    pub committer_date: Option<String>,
+    pub no_metadata: bool,
+    pub signoff: bool,
+    pub co_authors: Vec<String>,
+    pub closes: Vec<String>,
+    pub breaking: Option<String>,
+    pub interactive: bool,
+    pub identity: Option<String>,
+    pub message_prefix: Option<String>,
+    pub message_suffix: Option<String>,
+    pub paths: Vec<String>,
+    pub fixup: Option<String>,
+    pub tree_from: Option<String>,
+    pub assume_yes: bool,
+    pub wrap: bool,
+    pub amend: bool,
+    pub keep_message: bool,
+    pub from_editmsg: bool,
+    pub amend_author: Option<String>,
+    pub reset_committer: bool,
+    pub reflog_message: Option<String>,
+    pub message_encoding: Option<String>,
+    pub allow_empty: bool,
+    pub print_message: bool,
+    pub explain: bool,
 }
 
-pub fn finish() -> Result<(), Box<dyn std::error::Error>> {
-    let repo = Repository::open(".")?;
+pub fn finish(options: &FinishOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = Repository::open_from_env()?;
+    let mut config = load_config(&repo, &options.config_args)?;
+    if options.no_metadata {
+        config.use_metadata = Some(false);
+    }
+
+    // The first commit of a new repository has no parent
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+        Err(e) => return Err(e.into()),
+    };
+    let last_commit = || parent.as_ref().ok_or_else(|| t("no-commits-yet", &[]));
+
+    let identity = options.identity.as_deref().map(|name| config.identity(name)).transpose()?;
+    // An identity replaces both sides, otherwise the GIT_AUTHOR_*/GIT_COMMITTER_*
+    // variables win over the config like they do for git. `sig` is the committer
+    let (author_sig, sig) = match identity {
+        Some(identity) => {
+            let sig = Signature::now(&identity.name, &identity.email)?;
+            (sig.clone(), sig)
+        }
+        None => (signature(&repo, Role::Author)?, signature(&repo, Role::Committer)?),
+    };
+    let signer = Signer::from_config(&repo, identity.and_then(|identity| identity.signingkey.as_deref()))?;
+    let author = dated_signature(&author_sig, options.author_date.as_deref().or(options.date.as_deref()))?;
+    let committer = dated_signature(&sig, options.committer_date.as_deref().or(options.date.as_deref()))?;
+    let head_branch = head_branch(&repo)?;
+
+    // Only show the message, before anything is written
+    if options.print_message {
+        let current_branch = options.onto.as_deref().unwrap_or(&head_branch);
+        let commit_message = commit_message(&repo, &config, options, parent.as_ref(), current_branch, &committer)?;
+        explain(options, "final message", commit_message.trim_end());
+        print!("{}", commit_message);
+        return Ok(());
+    }
+
     let mut index = repo.index()?;
 
-    // Write the current index state to a tree
-    let tree_id = index.write_tree()?;
+    if let Some(new_author) = &options.amend_author {
+        let new_committer = options.reset_committer.then_some(&committer);
+        return amend_author(&repo, last_commit()?, &head_branch, new_author, new_committer, options.dry_run, options.assume_yes);
+    }
+
+    // Write the current index state to a tree. When only some paths (or the
+    // files picked interactively) are committed, HEAD's tree is updated with
+    // just those entries instead, and the rest stay staged
+    let tree_id = if let Some(tree_from) = &options.tree_from {
+        repo.revparse_single(tree_from)
+            .and_then(|object| object.peel_to_tree())
+            .map_err(|e| t("tree-not-found", &[("ref", tree_from), ("error", e.message())]))?
+            .id()
+    } else if options.interactive || !options.paths.is_empty() {
+        if options.interactive && !can_prompt(options.assume_yes) {
+            return Err(t("interactive-needs-terminal", &[]).into());
+        }
+        partial_tree(&repo, &index, parent.as_ref(), &options.paths, options.interactive)?
+    } else {
+        index.write_tree()?
+    };
     let tree = repo.find_tree(tree_id)?;
-    let sig = repo.signature()?;
-    let head = repo.head()?;
-    let parent = repo.find_commit(head.target().unwrap())?;
+
+    // Squashing replaces every commit unique to the branch, so the new commit's
+    // parent is the merge base with the base branch instead of HEAD
+    let squash_base = if options.squash {
+        let base = resolve_base(&repo, options.base.as_deref().or(config.base_branch.as_deref()))?;
+        warn_if_shallow(&repo, "--squash");
+        let merge_base = repo.merge_base(last_commit()?.id(), base.id()).map_err(|e| match e.code() {
+            ErrorCode::NotFound if repo.is_shallow() => {
+                t("no-merge-base-shallow", &[("commit", &base.id().to_string())])
+            }
+            _ => e.message().to_string(),
+        })?;
+        Some(repo.find_commit(merge_base)?)
+    } else {
+        None
+    };
+    // Only an explicit --base reports the branch's progress, base_branch doesn't
+    let progress_base = match &options.base {
+        Some(base) => Some(resolve_base(&repo, Some(base))?),
+        None => None,
+    };
+
+    // Amending replaces HEAD, so the new commit goes on top of HEAD's parent
+    let amend_base = if options.amend {
+        Some(last_commit()?.parent(0).map_err(|_| t("cannot-amend-root", &[]))?)
+    } else {
+        None
+    };
+    let new_parent = squash_base.as_ref().or(amend_base.as_ref()).or(parent.as_ref());
+
+    // Empty commits are refused unless asked for with --allow-empty, e.g. to
+    // trigger CI. Rewording doesn't change the tree either, so it skips the check
+    let unchanged = match new_parent {
+        Some(new_parent) => tree_id == new_parent.tree_id(),
+        None => tree.is_empty(),
+    };
+    if !options.reword && !options.allow_empty && unchanged {
+        return Err(t("nothing-to-commit", &[]).into());
+    }
+    if options.keep_message && !options.allow_empty && tree_id == last_commit()?.tree_id() {
+        return Err(t("keep-message-unchanged", &[]).into());
+    }
 
     // Get current branch name and read commit message from file
-    let current_branch = head.shorthand().ok_or("Could not get current branch name")?;
-    let message_file = get_gwf_dir().join(slugify(current_branch));
-    let message = fs::read_to_string(message_file)?;
-
-    // Extract type and scope from branch name (format: type/scope/message or type/message)
-    let parts: Vec<&str> = current_branch.split('/').collect();
-    let (type_, scope) = if parts.len() == 2 {
-        (parts[0], "")
-    } else if parts.len() == 3 {
-        (parts[0], parts[1])
+    let current_branch = match &options.onto {
+        Some(onto) => onto.as_str(),
+        None => head_branch.as_str(),
+    };
+    // A new repository has no other branch to start from, so its first commit
+    // may go on a protected branch
+    if !options.force && parent.is_some() && config.is_protected(current_branch) {
+        return Err(t("protected-branch", &[("branch", current_branch)]).into());
+    }
+
+    // Catch commits made with the wrong identity, e.g. a personal email at work
+    if let Some(domain) = &config.expected_author_domain {
+        let email = sig.email().unwrap_or_default();
+        if !email.ends_with(&format!("@{}", domain.trim_start_matches('@'))) {
+            if !options.force {
+                return Err(t("unexpected-domain", &[("email", email), ("domain", domain)]).into());
+            }
+            eprintln!("{}", t("unexpected-domain-forced", &[("email", email), ("domain", domain)]));
+        }
+    }
+
+    let commit_message = commit_message(&repo, &config, options, parent.as_ref(), current_branch, &committer)?;
+    explain(options, "final message", commit_message.trim_end());
+    if let Some(width) = config.max_body_line_length {
+        let long_lines = long_body_lines(&commit_message, width);
+        if !long_lines.is_empty() {
+            let numbers: Vec<String> = long_lines.iter().map(usize::to_string).collect();
+            eprintln!("{}", t("long-lines", &[("width", &width.to_string()), ("lines", &numbers.join(", "))]));
+        }
+    }
+
+    if options.reword {
+        // Only replace a signature whose date or identity was asked for
+        let dated_author = options.author_date.is_some() || options.date.is_some() || identity.is_some();
+        let dated_committer = options.committer_date.is_some() || options.date.is_some() || identity.is_some();
+        let signatures = (dated_author.then_some(&author), dated_committer.then_some(&committer));
+        return reword(&repo, last_commit()?, current_branch, &commit_message, signatures, options.dry_run, options.assume_yes);
+    }
+
+    // Refuse up front so an existing tag doesn't fail after committing
+    if let Some(tag) = &options.tag {
+        if !options.force && repo.find_reference(&format!("refs/tags/{}", tag)).is_ok() {
+            return Err(t("tag-exists", &[("tag", tag)]).into());
+        }
+    }
+    if let Some(onto) = &options.onto {
+        if repo.find_branch(onto, BranchType::Local).is_ok() {
+            return Err(t("onto-exists", &[("branch", onto)]).into());
+        }
+    }
+
+    if options.show_diff || options.dry_run {
+        let parent_tree = new_parent.map(|new_parent| new_parent.tree()).transpose()?;
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+        print_diff(&diff)?;
+    }
+
+    let squashed = match &squash_base {
+        Some(squash_base) => count_commits(&repo, last_commit()?.id(), squash_base.id())?,
+        None => 0,
+    };
+
+    // Like `git commit --amend`, the original author is kept unless a date or
+    // identity was asked for
+    let author = if options.amend && options.author_date.is_none() && options.date.is_none() && identity.is_none() {
+        last_commit()?.author().to_owned()
     } else {
-        return Err("Invalid branch name format. Expected: type/scope/message or type/message".into());
+        author
     };
-    
-    // Construct conventional commit message
-    let commit_message = if scope.is_empty() {
-        format!("{}: {}", type_, message)
+
+    if options.amend && !options.dry_run {
+        let amended = last_commit()?;
+        if let Some(upstream) = pushed_to(&repo, current_branch, amended.id()) {
+            let prompt = t("confirm-amend-pushed", &[("commit", &amended.id().to_string()), ("upstream", &upstream)]);
+            if !confirm(&prompt, false, options.assume_yes)? {
+                return Err(t("aborted-amend-pushed", &[]).into());
+            }
+        }
+    }
+
+    let encoded_message = match &options.message_encoding {
+        Some(encoding) => encode_message(&commit_message, encoding)?,
+        None => None,
+    };
+
+    let header = parse_conventional(commit_message.lines().next().unwrap_or_default()).ok();
+    let post_commit_command = config.post_commit_command_for(header.as_ref().map(|h| h.type_), header.as_ref().and_then(|h| h.scope));
+
+    if options.dry_run {
+        if let Some(onto) = &options.onto {
+            println!("{}", t("would-create-onto", &[("branch", onto)]));
+        }
+        if options.amend {
+            println!("{}", t("would-amend", &[("commit", &last_commit()?.id().to_string())]));
+        }
+        if let Some(squash_base) = &squash_base {
+            println!("{}", t("would-squash", &[("count", &squashed.to_string()), ("commit", &squash_base.id().to_string())]));
+        }
+        println!("{}", t("would-create-commit", &[("branch", current_branch), ("message", commit_message.trim_end())]));
+        if let Some(post_commit_command) = post_commit_command {
+            println!("\n{}", t("would-run-post-commit", &[("command", post_commit_command)]));
+        }
+        if let Some(tag) = &options.tag {
+            let key = if options.annotated { "would-create-annotated-tag" } else { "would-create-lightweight-tag" };
+            println!("\n{}", t(key, &[("tag", tag)]));
+        }
+        return Ok(());
+    }
+
+    // Set aside unstaged work so the commit and post-commit command only see the staged set
+    let stash = if options.stash { stash_unstaged(&repo, &sig)? } else { None };
+
+    // The reflog entry names the gwf command unless one was given
+    let reflog_message = options.reflog_message.clone().unwrap_or_else(|| {
+        let mode = match (&squash_base, &amend_base) {
+            (Some(_), _) => " --squash",
+            (None, Some(_)) => " --amend",
+            (None, None) => "",
+        };
+        format!("gwf finish{}: {}", mode, commit_message.lines().next().unwrap_or_default())
+    });
+
+    let result = (|| -> Result<(), Box<dyn std::error::Error>> {
+        if config.run_pre_commit.unwrap_or(false) {
+            run_pre_commit(&repo)?;
+        }
+        // The branch is only created once every check passed, so a failed
+        // finish leaves HEAD where it was
+        if let Some(onto) = &options.onto {
+            if parent.is_none() {
+                // There is nothing to check out, the first commit just lands on the new branch
+                repo.set_head(&format!("refs/heads/{}", onto))?;
+            } else {
+                create_and_checkout(&repo, onto, false)?;
+            }
+            println!("{}", t("branch-created", &[("branch", onto)]));
+        }
+
+        // Create the commit, the cases only differ in its parents
+        let write = |parents: &[&Commit]| match &encoded_message {
+            Some(encoded) => write_encoded_commit(&repo, &author, &committer, encoded, &tree, parents, signer.as_ref()),
+            None => write_commit(&repo, &author, &committer, &commit_message, &tree, parents, signer.as_ref()),
+        };
+        let commit_id = match (&squash_base, &amend_base) {
+            (Some(squash_base), _) => {
+                // HEAD is not the new commit's parent, so the branch moves past
+                // the squashed commits
+                let commit_id = write(&[squash_base])?;
+                repo.head()?.set_target(commit_id, &reflog_message)?;
+                println!("{}", t("squashed", &[("count", &squashed.to_string()), ("commit", &squash_base.id().to_string())]));
+                commit_id
+            }
+            (None, Some(amend_base)) => {
+                let commit_id = write(&[amend_base])?;
+                repo.head()?.set_target(commit_id, &reflog_message)?;
+                println!("{}", t("amended-commit", &[("commit", &last_commit()?.id().to_string())]));
+                commit_id
+            }
+            (None, None) => {
+                let parents: Vec<&Commit> = parent.iter().collect();
+                let commit_id = write(&parents)?;
+                update_head(&repo, commit_id, &reflog_message)?;
+                commit_id
+            }
+        };
+
+        println!("{}", t("created-commit", &[("commit", &commit_id.to_string())]));
+        if let (Some(base), Some(name)) = (&progress_base, &options.base) {
+            let (ahead, behind) = repo.graph_ahead_behind(commit_id, base.id())?;
+            let (ahead, behind) = (ahead.to_string(), behind.to_string());
+            println!("{}", t("ahead-behind", &[("branch", current_branch), ("ahead", &ahead), ("base", name), ("behind", &behind)]));
+        }
+        if let Some(tree_from) = &options.tree_from {
+            println!("{}", t("committed-tree-from", &[("ref", tree_from)]));
+        }
+
+        // Kept so a rollback can put a moved tag back
+        let previous_tag = options
+            .tag
+            .as_ref()
+            .and_then(|tag| repo.find_reference(&format!("refs/tags/{}", tag)).ok())
+            .and_then(|reference| reference.target());
+
+        if let Some(tag) = &options.tag {
+            let target = repo.find_object(commit_id, None)?;
+            if options.annotated {
+                let tag_message = options.tag_message.as_deref().unwrap_or(&commit_message);
+                repo.tag(tag, &target, &sig, tag_message, options.force)?;
+            } else {
+                repo.tag_lightweight(tag, &target, options.force)?;
+            }
+            println!("{}", t("created-tag", &[("tag", tag)]));
+        }
+
+        // Run the post-commit command if one is configured
+        if let Some(post_commit_command) = post_commit_command {
+            let commit = commit_id.to_string();
+            let values = [
+                ("branch", current_branch),
+                ("commit", commit.as_str()),
+                ("type", header.as_ref().map(|h| h.type_).unwrap_or_default()),
+                ("scope", header.as_ref().and_then(|h| h.scope).unwrap_or_default()),
+                ("subject", header.as_ref().map(|h| h.subject).unwrap_or_default()),
+                ("tag", options.tag.as_deref().unwrap_or_default()),
+            ];
+            let post_commit_command = expand_command(post_commit_command, &values);
+            let succeeded = run_post_commit_command(&post_commit_command, options.quiet, options.raw_output)?;
+            let optional = options.ignore_post_commit_failure || config.post_commit_optional.unwrap_or(false);
+            if !succeeded && !optional {
+                if config.rollback_on_post_commit_failure.unwrap_or(false) {
+                    rollback(&repo, parent.as_ref(), options.tag.as_deref(), previous_tag)?;
+                    return Err(t("post-commit-rolled-back", &[("commit", &commit_id.to_string())]).into());
+                }
+                return Err(t("post-commit-failed", &[("commit", &commit_id.to_string())]).into());
+            }
+        }
+        Ok(())
+    })();
+
+    if let Some((mut stash_repo, stash_id)) = stash {
+        restore_stash(&mut stash_repo, stash_id);
+    }
+
+    result
+}
+
+// With --explain, print one step of how the message was put together to
+// stderr, indenting multi-line details below it. The trace is a debugging
+// aid and stays in English, like the config keys and flags it refers to
+fn explain(options: &FinishOptions, step: &str, detail: &str) {
+    if !options.explain {
+        return;
+    }
+    if detail.contains('\n') {
+        eprintln!("explain: {}:", step);
+        for line in detail.lines() {
+            eprintln!("{}", format!("    {}", line).trim_end());
+        }
     } else {
-        format!("{}({}): {}", type_, scope, message)
-    };
-
-    // Create the commit
-    let commit_id = repo.commit(
-        Some("HEAD"),
-        &sig,
-        &sig,
-        &commit_message,
-        &tree,
-        &[&parent]
-    )?;
-
-    println!("Created commit: {}", commit_id);
-
-    // Try to read and execute the post-commit command if config exists
-    // First try repository root, then .gwf directory
-    let repo_root = repo.workdir().ok_or("Could not get repository root")?;
-    let config_file = repo_root.join(GWF_CONFIG);
-    let config_content = if config_file.exists() {
-        fs::read_to_string(config_file)
+        eprintln!("explain: {}: {}", step, detail);
+    }
+}
+
+// The message finish commits, before any check that it is conventional
+fn commit_message(
+    repo: &Repository,
+    config: &Config,
+    options: &FinishOptions,
+    parent: Option<&Commit>,
+    current_branch: &str,
+    committer: &Signature,
+) -> Result<String, Box<dyn std::error::Error>> {
+    // A kept message is reused verbatim, without rebuilding or normalizing it
+    if options.keep_message {
+        let parent = parent.ok_or_else(|| t("no-commits-yet", &[]))?;
+        return Ok(String::from_utf8_lossy(parent.message_bytes()).into_owned());
+    }
+    let commit_message = match &options.fixup {
+        Some(target) => {
+            explain(options, "message", &format!("(a fixup of {})", target));
+            fixup_message(repo, target)?
+        }
+        None if options.from_editmsg => {
+            explain(options, "message", "(from COMMIT_EDITMSG)");
+            editmsg_message(repo)?
+        }
+        None => {
+            // Amending keeps the trailers of the commit it replaces
+            let amended = if options.amend { parent } else { None };
+            build_message(repo, config, options, current_branch, committer, parent.is_none(), amended)?
+        }
+    };
+    let commit_message = if options.edit { edit_message(repo, &commit_message)? } else { commit_message };
+    Ok(normalize_message(&commit_message))
+}
+
+// The conventional commit message for the branch: header, body, template and trailers
+fn build_message(
+    repo: &Repository,
+    config: &Config,
+    options: &FinishOptions,
+    current_branch: &str,
+    committer: &Signature,
+    initial: bool,
+    amended: Option<&Commit>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let explain = |step: &str, detail: &str| explain(options, step, detail);
+    explain("branch", current_branch);
+
+    // Without metadata everything comes from the branch name
+    let metadata = if config.uses_metadata() {
+        let metadata_path = metadata_file(repo, config, current_branch)?;
+        let metadata = read_metadata(&metadata_path)?;
+        if options.explain {
+            let contents = fs::read(&metadata_path).map(|bytes| String::from_utf8_lossy(&bytes).trim_end().to_string());
+            explain("metadata file", &metadata_path.display().to_string());
+            explain("metadata contents", contents.as_deref().unwrap_or("(missing)"));
+        }
+        if metadata.is_none() && options.message.is_none() && !initial {
+            eprintln!("{}", t("metadata-missing", &[("path", &metadata_path.display().to_string())]));
+        }
+        metadata.unwrap_or_default()
     } else {
-        fs::read_to_string(get_gwf_dir().join(GWF_CONFIG))
-    };
-
-    if let Ok(config_content) = config_content {
-        if let Ok(config) = toml::from_str::<Config>(&config_content) {
-            // Run the post-commit command
-            let output = ExternalCommand::new("sh")
-                .arg("-c")
-                .arg(&config.post_commit_command)
-                .output()?;
-            
-            // Print stdout if not empty
-            if !output.stdout.is_empty() {
-                println!("Post-commit command output:\n{}", String::from_utf8_lossy(&output.stdout));
+        explain("metadata file", "(disabled by use_metadata)");
+        BranchMetadata::default()
+    };
+
+    let (message, message_source) = match &options.message {
+        Some(message) => (message.clone(), "--message"),
+        None if metadata.message.is_empty() && initial => (INITIAL_COMMIT_MESSAGE.to_string(), "the first commit default"),
+        None if metadata.message.is_empty() => (message_from_branch(current_branch), "the branch name"),
+        None => (metadata.message.clone(), "the metadata"),
+    };
+    explain(&format!("message (from {})", message_source), &message);
+
+    // Type and scope come from the stored metadata, falling back to the branch
+    // name for older metadata (format: type/scope/message or type/message)
+    let parsed_branch = parse_branch(current_branch);
+    let branch_type = parsed_branch.as_ref().map(|b| b.type_);
+    let branch_scope = parsed_branch.as_ref().map(|b| b.scope).unwrap_or_default();
+    match &parsed_branch {
+        Some(parts) => explain(
+            "branch parts",
+            &format!("type `{}`, scope `{}`, message `{}`", parts.type_, parts.scope, parts.message),
+        ),
+        None => explain("branch parts", "(not type/scope/message or type/message)"),
+    }
+    let (type_, type_source) = match (&options.type_, &metadata.type_, branch_type) {
+        (Some(type_), _, _) => (type_.as_str(), "--type"),
+        (None, Some(type_), _) => (type_.as_str(), "the metadata"),
+        (None, None, Some(type_)) => (type_, "the branch name"),
+        (None, None, None) if initial => (INITIAL_COMMIT_TYPE, "the first commit default"),
+        (None, None, None) => {
+            return Err(t("invalid-branch-format", &[]).into())
+        }
+    };
+    let (scope, scope_source) = match (&options.scope, &metadata.scope) {
+        (Some(scope), _) => (scope.as_str(), "--scope"),
+        (None, Some(scope)) => (scope.as_str(), "the metadata"),
+        (None, None) => (branch_scope, "the branch name"),
+    };
+    explain(&format!("type (from {})", type_source), type_);
+    explain(&format!("scope (from {})", scope_source), if scope.is_empty() { "(none)" } else { scope });
+
+    // Separate the subject from any body paragraphs, moving the overflow of
+    // long subjects into the body if configured
+    let (subject, body) = split_subject(&message, config.autowrap_body.unwrap_or(usize::MAX));
+    // Notes added with `gwf note` go after the stored body
+    let body = match (body, metadata.notes.is_empty()) {
+        (body, true) => body,
+        (Some(body), false) => Some(format!("{}\n\n{}", body.trim_end(), metadata.notes.join("\n"))),
+        (None, false) => Some(metadata.notes.join("\n")),
+    };
+    let body = match (body, config.max_body_line_length) {
+        (Some(body), Some(width)) if options.wrap => Some(wrap_body(&body, width)),
+        (body, _) => body,
+    };
+
+    // Decorate the subject, keeping the conventional header in front
+    let prefix = options.message_prefix.as_deref().or(config.message_prefix.as_deref());
+    let suffix = options.message_suffix.as_deref().or(config.message_suffix.as_deref());
+    let decorated_subject = match (prefix, suffix) {
+        (None, None) => subject.clone(),
+        _ => [prefix.unwrap_or_default(), subject.as_str(), suffix.unwrap_or_default()]
+            .iter()
+            .map(|part| part.trim())
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "),
+    };
+
+    // Construct conventional commit message
+    let header_template = config.header_template.as_deref().unwrap_or(DEFAULT_HEADER_TEMPLATE);
+    let mut commit_message = render_template(
+        header_template,
+        &[
+            ("type", type_),
+            ("scope", scope),
+            ("subject", &decorated_subject),
+            ("!", if options.breaking.is_some() { "!" } else { "" }),
+        ],
+    );
+    parse_conventional(&commit_message).map_err(|e| t("invalid-header", &[("header", &commit_message), ("error", &e.to_string())]))?;
+    if let Some(body) = body {
+        commit_message = format!("{}\n\n{}", commit_message.trim_end(), body);
+    }
+
+    let placeholders = [
+        ("type", type_),
+        ("scope", scope),
+        ("subject", subject.trim()),
+        ("branch", current_branch),
+    ];
+
+    // Add the team's body template for the type with its placeholders filled in
+    if let Some(template) = config.body_template(repo, type_)? {
+        let template = fill_placeholders(&template, &placeholders);
+        commit_message = format!("{}\n\n{}", commit_message.trim_end(), template.trim());
+    }
+
+    let mut trailers = Trailers::default();
+    if let Some(amended) = amended {
+        trailers.add_from(&String::from_utf8_lossy(amended.message_bytes()));
+    }
+    if let Some(breaking) = &options.breaking {
+        trailers.add(config.breaking_change_token()?, breaking);
+    }
+
+    // Reference the ticket mentioned in the message, e.g. "PROJ-123 add thing"
+    if let Some(pattern) = &config.ticket_pattern {
+        let pattern = Regex::new(pattern).map_err(|e| t("invalid-ticket-pattern", &[("error", &e.to_string())]))?;
+        if let Some(captures) = pattern.captures(&message) {
+            let ticket = captures.get(1).or_else(|| captures.get(0)).map(|m| m.as_str()).unwrap_or_default();
+            let footer = config.ticket_footer.as_deref().unwrap_or(DEFAULT_TICKET_FOOTER);
+            trailers.add(footer, ticket);
+        }
+    }
+    // Trailers every commit must carry, asking for any value left unfilled
+    for template in config.trailers.iter().flatten() {
+        let trailer = fill_placeholders(template, &placeholders);
+        let (key, value) = trailer
+            .split_once(':')
+            .ok_or_else(|| t("invalid-trailer", &[("trailer", template)]))?;
+        let value = prompt_placeholders(key.trim(), value.trim(), options.assume_yes)?;
+        trailers.add(key.trim(), &value);
+    }
+    for issue in &options.closes {
+        trailers.add("Closes", issue);
+    }
+    for co_author in &options.co_authors {
+        trailers.add("Co-authored-by", co_author);
+    }
+    if options.signoff {
+        let name = String::from_utf8_lossy(committer.name_bytes());
+        let email = String::from_utf8_lossy(committer.email_bytes());
+        trailers.add("Signed-off-by", &format!("{} <{}>", name, email));
+    }
+    Ok(trailers.append_to(&commit_message))
+}
+
+// The message left in .git/COMMIT_EDITMSG by git's editor flow, used as is
+// apart from dropping comments. A header that isn't conventional only warns,
+// since the message was written by hand
+fn editmsg_message(repo: &Repository) -> Result<String, Box<dyn std::error::Error>> {
+    let path = repo.path().join("COMMIT_EDITMSG");
+    let bytes = fs::read(&path).context("read commit message", &path)?;
+    let message = strip_comments(&lossy_utf8(bytes, &path.display().to_string()));
+    if message.is_empty() {
+        return Err(t("no-message-in-file", &[("path", &path.display().to_string())]).into());
+    }
+    let header = message.lines().next().unwrap_or_default();
+    if let Err(e) = parse_conventional(header) {
+        let path = path.display().to_string();
+        eprintln!("{}", t("file-header-not-conventional", &[("header", header), ("path", &path), ("error", &e.to_string())]));
+    }
+    Ok(message)
+}
+
+// "fixup! <subject>" for the target commit, as `git commit --fixup` writes it
+fn fixup_message(repo: &Repository, target: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let commit = repo
+        .revparse_single(target)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| t("fixup-target-missing", &[("commit", target), ("error", e.message())]))?;
+    Ok(format!("fixup! {}", String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default())))
+}
+
+// Tree with the staged changes under `pathspecs` (all of them when empty)
+// applied on top of HEAD, optionally letting the user pick the files. Only the
+// matching index entries and the trees above them are visited or rewritten.
+fn partial_tree(
+    repo: &Repository,
+    index: &Index,
+    head_commit: Option<&Commit>,
+    pathspecs: &[String],
+    interactive: bool,
+) -> Result<Oid, Box<dyn std::error::Error>> {
+    // The first commit starts from an empty tree
+    let head_tree = match head_commit {
+        Some(head_commit) => head_commit.tree()?,
+        None => repo.find_tree(repo.treebuilder(None)?.write()?)?,
+    };
+    let mut diff_opts = DiffOptions::new();
+    for pathspec in pathspecs {
+        diff_opts.pathspec(pathspec);
+    }
+    let diff = repo.diff_tree_to_index(Some(&head_tree), Some(index), Some(&mut diff_opts))?;
+    let mut paths: Vec<PathBuf> = diff
+        .deltas()
+        .filter_map(|delta| delta.new_file().path().or(delta.old_file().path()).map(Path::to_path_buf))
+        .collect();
+    if paths.is_empty() {
+        return Err(if pathspecs.is_empty() {
+            t("nothing-staged", &[])
+        } else {
+            t("nothing-staged-under", &[("paths", &pathspecs.join(", "))])
+        }
+        .into());
+    }
+
+    if interactive {
+        let labels: Vec<String> = paths.iter().map(|path| path.display().to_string()).collect();
+        let selected = MultiSelect::new()
+            .with_prompt(t("select-staged-files", &[]))
+            .items(&labels)
+            .defaults(&vec![true; labels.len()])
+            .interact()?;
+        if selected.is_empty() {
+            return Err(t("no-files-selected", &[]).into());
+        }
+        paths = selected.into_iter().map(|i| paths[i].clone()).collect();
+    }
+
+    let mut update = TreeUpdateBuilder::new();
+    for path in &paths {
+        match index.get_path(path, 0) {
+            Some(entry) => {
+                let mode = match entry.mode {
+                    0o100755 => FileMode::BlobExecutable,
+                    0o120000 => FileMode::Link,
+                    0o160000 => FileMode::Commit,
+                    _ => FileMode::Blob,
+                };
+                update.upsert(path, entry.id, mode);
             }
-            
-            // Print stderr if not empty
-            if !output.stderr.is_empty() {
-                eprintln!("Post-commit command errors:\n{}", String::from_utf8_lossy(&output.stderr));
+            None => {
+                update.remove(path);
+            }
+        }
+    }
+    Ok(update.create_updated(repo, &head_tree)?)
+}
+
+// Ask for every `{...}` placeholder still left in a trailer value
+fn prompt_placeholders(key: &str, value: &str, assume_yes: bool) -> Result<String, Box<dyn std::error::Error>> {
+    let placeholder = Regex::new(r"\{[^{}]*\}")?;
+    if !placeholder.is_match(value) {
+        return Ok(value.to_string());
+    }
+    if !can_prompt(assume_yes) {
+        return Err(t("missing-trailer-value", &[("key", key), ("value", value)]).into());
+    }
+    replace_placeholders(&placeholder, value, |name| {
+        let prompt = if name.is_empty() { key.to_string() } else { format!("{} ({})", key, name) };
+        Ok(Input::new().with_prompt(prompt).interact_text()?)
+    })
+}
+
+// Replace each placeholder of the original value with its answer. Answers
+// are not searched again, so one containing `{...}` is kept as typed
+fn replace_placeholders(
+    placeholder: &Regex,
+    value: &str,
+    mut answer: impl FnMut(&str) -> Result<String, Box<dyn std::error::Error>>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut filled = String::new();
+    let mut end = 0;
+    for found in placeholder.find_iter(value) {
+        filled.push_str(&value[end..found.start()]);
+        filled.push_str(answer(found.as_str().trim_matches(['{', '}']))?.trim());
+        end = found.end();
+    }
+    filled.push_str(&value[end..]);
+    Ok(filled)
+}
+
+// Run the pre-commit framework's hooks when the repository is set up for it
+fn run_pre_commit(repo: &Repository) -> Result<(), Box<dyn std::error::Error>> {
+    let repo_root = repo.workdir().ok_or_else(|| t("no-repo-root", &[]))?;
+    if !repo_root.join(PRE_COMMIT_CONFIG).exists() {
+        return Ok(());
+    }
+    let output = match ExternalCommand::new("pre-commit")
+        .args(["run", "--hook-stage", "pre-commit"])
+        .current_dir(repo_root)
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            eprintln!("{}", t("pre-commit-missing", &[("config", PRE_COMMIT_CONFIG)]));
+            return Ok(());
+        }
+        Err(e) => return Err(t("pre-commit-not-run", &[("error", &e.to_string())]).into()),
+    };
+    if output.status.success() {
+        return Ok(());
+    }
+
+    print!("{}", String::from_utf8_lossy(&output.stdout));
+    eprint!("{}", String::from_utf8_lossy(&output.stderr));
+    // Result lines look like "hook name.......Failed"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let failed: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.trim_end().ends_with("Failed"))
+        .map(|line| line.split("..").next().unwrap_or(line).trim())
+        .collect();
+    if failed.is_empty() {
+        Err(t("pre-commit-failed", &[("code", &output.status.code().unwrap_or(-1).to_string())]).into())
+    } else {
+        Err(t("pre-commit-hooks-failed", &[("hooks", &failed.join(", "))]).into())
+    }
+}
+
+// Undo a just-created commit: move the branch back to where it was, keeping
+// the index (and so the staged changes), and put any tag back
+fn rollback(repo: &Repository, previous_head: Option<&Commit>, tag: Option<&str>, previous_tag: Option<Oid>) -> Result<(), Box<dyn std::error::Error>> {
+    eprintln!("{}", t("rolling-back", &[]));
+    match previous_head {
+        Some(previous_head) => repo.reset(previous_head.as_object(), ResetType::Soft, None)?,
+        // Undoing the first commit leaves the branch unborn again
+        None => repo.head()?.delete()?,
+    }
+    if let Some(tag) = tag {
+        let tag_ref = format!("refs/tags/{}", tag);
+        match previous_tag {
+            Some(previous) => {
+                repo.reference(&tag_ref, previous, true, "gwf finish: roll back tag")?;
             }
-            
-            if output.status.success() {
-                println!("Post-commit command executed successfully");
+            None => repo.find_reference(&tag_ref)?.delete()?,
+        }
+    }
+    match previous_head {
+        Some(previous_head) => eprintln!("{}", t("rolled-back", &[("commit", &previous_head.id().to_string())])),
+        None => eprintln!("{}", t("rolled-back-unborn", &[])),
+    }
+    Ok(())
+}
+
+// Expansion happens in two phases. gwf first replaces its {placeholders} with
+// shell-quoted values, so a subject like `fix $HOME` stays literal, then the
+// shell expands environment variables ($VAR, or %VAR% with cmd) as usual.
+// cmd has no quoting that stops %VAR% expansion, so on Windows a value
+// containing `%HOME%` is still expanded.
+fn expand_command(command: &str, values: &[(&str, &str)]) -> String {
+    let quoted: Vec<(&str, String)> = values.iter().map(|(name, value)| (*name, shell_quote(value))).collect();
+    let quoted: Vec<(&str, &str)> = quoted.iter().map(|(name, value)| (*name, value.as_str())).collect();
+    fill_placeholders(command, &quoted)
+}
+
+#[cfg(not(windows))]
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(windows)]
+fn shell_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+// Run the post-commit command through the shell, returning whether it succeeded
+fn run_post_commit_command(post_commit_command: &str, quiet: bool, raw: bool) -> Result<bool, Box<dyn std::error::Error>> {
+    #[cfg(not(windows))]
+    let mut command = {
+        let mut command = ExternalCommand::new("sh");
+        command.arg("-c").arg(post_commit_command);
+        command
+    };
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = ExternalCommand::new("cmd");
+        command.arg("/C").arg(post_commit_command);
+        command
+    };
+    let output = command.output()?;
+
+    // Quiet mode still shows the output of a failing command
+    let show_output = !quiet || !output.status.success();
+
+    // Print stdout if not empty
+    if show_output && !output.stdout.is_empty() {
+        println!("{}\n{}", t("post-commit-output", &[]), command_output(&output.stdout, raw));
+    }
+
+    // Print stderr if not empty
+    if show_output && !output.stderr.is_empty() {
+        eprintln!("{}\n{}", t("post-commit-errors", &[]), command_output(&output.stderr, raw));
+    }
+
+    if output.status.success() {
+        if !quiet {
+            println!("{}", t("post-commit-succeeded", &[]));
+        }
+    } else {
+        eprintln!("{}", t("post-commit-exit-code", &[("code", &output.status.code().unwrap_or(-1).to_string())]));
+    }
+
+    Ok(output.status.success())
+}
+
+// Command output for display: CRLF line endings become LF, a line redrawn
+// with bare carriage returns keeps only what was drawn last, and trailing
+// whitespace is dropped. `raw` passes the output through untouched.
+fn command_output(bytes: &[u8], raw: bool) -> String {
+    let output = String::from_utf8_lossy(bytes);
+    if raw {
+        return output.into_owned();
+    }
+    output
+        .lines()
+        .map(|line| line.rsplit('\r').find(|part| !part.is_empty()).unwrap_or("").trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Stash unstaged changes, leaving the index alone. Returns a separate handle
+// on the repository and the stash commit to restore from, or None if there was
+// nothing to stash.
+fn stash_unstaged(repo: &Repository, sig: &Signature) -> Result<Option<(Repository, Oid)>, Box<dyn std::error::Error>> {
+    let mut stash_repo = Repository::open(repo.path())?;
+    match stash_repo.stash_save(sig, "gwf finish --stash", Some(StashFlags::KEEP_INDEX)) {
+        Ok(stash_id) => {
+            println!("{}", t("stashed", &[]));
+            Ok(Some((stash_repo, stash_id)))
+        }
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// Put the stashed working tree back without touching the index. The stash
+// already contains the staged changes that are now committed, so restoring its
+// tree can't conflict with the new HEAD. The stash is kept if that fails.
+fn restore_stash(stash_repo: &mut Repository, stash_id: Oid) {
+    let restored = stash_repo.find_commit(stash_id).and_then(|stash| {
+        let mut checkout_opts = git2::build::CheckoutBuilder::new();
+        checkout_opts.force().update_index(false);
+        stash_repo.checkout_tree(stash.as_object(), Some(&mut checkout_opts))
+    });
+    match restored {
+        Ok(()) => {
+            if let Err(e) = stash_repo.stash_drop(0) {
+                eprintln!("{}", t("stash-not-dropped", &[("error", &e.to_string())]));
             } else {
-                eprintln!("Post-commit command failed with exit code: {}", output.status.code().unwrap_or(-1));
+                println!("{}", t("stash-restored", &[]));
             }
         }
+        Err(e) => eprintln!("{}", t("stash-not-restored", &[("error", &e.to_string())])),
+    }
+}
+
+// Let the user edit the message in their git editor, dropping comment lines
+fn edit_message(repo: &Repository, message: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mut editor = Editor::new();
+    let git_editor = env::var("GIT_EDITOR")
+        .ok()
+        .or_else(|| repo.config().ok()?.get_string("core.editor").ok());
+    if let Some(git_editor) = &git_editor {
+        editor.executable(git_editor);
     }
 
+    let instructions: Vec<String> = t("editor-instructions", &[]).lines().map(|line| format!("# {}", line)).collect();
+    let initial = format!("{}\n\n{}\n", message.trim_end(), instructions.join("\n"));
+    let edited = editor.edit(&initial)?.ok_or_else(|| t("editor-not-saved", &[]))?;
+    let edited = strip_comments(&edited);
+    if edited.trim().is_empty() {
+        return Err(t("editor-empty", &[]).into());
+    }
+    Ok(edited)
+}
+
+// Commit the feature branch is based on, from --base or the base_branch config
+fn resolve_base<'a>(repo: &'a Repository, base: Option<&str>) -> Result<Commit<'a>, Box<dyn std::error::Error>> {
+    let base = base.ok_or_else(|| t("no-base-branch", &[]))?;
+    let commit = repo
+        .revparse_single(base)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|_| t("base-branch-missing", &[("branch", base)]))?;
+    Ok(commit)
+}
+
+// Number of commits reachable from `from` but not from `base`
+fn count_commits(repo: &Repository, from: Oid, base: Oid) -> Result<usize, git2::Error> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(from)?;
+    revwalk.hide(base)?;
+    Ok(commits(repo, revwalk).count())
+}
+
+// Signatures (author, committer) to replace on the reworded commit, None keeps the original
+type Signatures<'a, 'b> = (Option<&'a Signature<'b>>, Option<&'a Signature<'b>>);
+
+// Replace the message of the HEAD commit, keeping its tree and parents
+fn reword(
+    repo: &Repository,
+    head_commit: &Commit,
+    branch: &str,
+    message: &str,
+    (author, committer): Signatures,
+    dry_run: bool,
+    assume_yes: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if dry_run {
+        println!("{}", t("would-reword", &[("commit", &head_commit.id().to_string()), ("message", message.trim_end())]));
+        return Ok(());
+    }
+
+    if let Some(upstream) = pushed_to(repo, branch, head_commit.id()) {
+        let prompt = t("confirm-rewrite-pushed", &[("commit", &head_commit.id().to_string()), ("upstream", &upstream)]);
+        let confirmed = confirm(&prompt, false, assume_yes)?;
+        if !confirmed {
+            return Err(t("aborted-reword-pushed", &[]).into());
+        }
+    }
+
+    // The new message is UTF-8, so don't keep another encoding the original declared
+    let encoding = head_commit.message_encoding().map(|_| "UTF-8");
+    let commit_id = head_commit.amend(Some("HEAD"), author, committer, encoding, Some(message), None)?;
+    println!("{}", t("reworded-commit", &[("commit", &commit_id.to_string())]));
+    Ok(())
+}
+
+// Replace the author of the HEAD commit, keeping its message, tree and
+// author date. The committer is only replaced when given
+fn amend_author(
+    repo: &Repository,
+    head_commit: &Commit,
+    branch: &str,
+    new_author: &str,
+    committer: Option<&Signature>,
+    dry_run: bool,
+    assume_yes: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (name, email) = parse_author(new_author)?;
+    let author = Signature::new(name, email, &head_commit.author().when())?;
+    if dry_run {
+        println!("{}", t("would-change-author", &[("commit", &head_commit.id().to_string()), ("name", name), ("email", email)]));
+        return Ok(());
+    }
+
+    if let Some(upstream) = pushed_to(repo, branch, head_commit.id()) {
+        let prompt = t("confirm-rewrite-pushed", &[("commit", &head_commit.id().to_string()), ("upstream", &upstream)]);
+        if !confirm(&prompt, false, assume_yes)? {
+            return Err(t("aborted-change-author-pushed", &[]).into());
+        }
+    }
+
+    let commit_id = head_commit.amend(Some("HEAD"), Some(&author), committer, None, None, None)?;
+    let (old_id, new_id) = (head_commit.id().to_string(), commit_id.to_string());
+    println!("{}", t("changed-author", &[("commit", &old_id), ("name", name), ("email", email), ("new", &new_id)]));
+    Ok(())
+}
+
+// Split "Name <email>" into its parts
+fn parse_author(author: &str) -> Result<(&str, &str), Box<dyn std::error::Error>> {
+    let invalid = || t("invalid-author", &[("author", author)]);
+    let (name, email) = author.trim().strip_suffix('>').and_then(|rest| rest.rsplit_once('<')).ok_or_else(invalid)?;
+    let (name, email) = (name.trim(), email.trim());
+    if name.is_empty() || !email.contains('@') || email.contains(['<', '>']) {
+        return Err(invalid().into());
+    }
+    Ok((name, email))
+}
+
+// How to sign commits when commit.gpgsign is enabled
+struct Signer {
+    program: String,
+    key: Option<String>,
+}
+
+impl Signer {
+    // None unless commit.gpgsign is set. The identity's signing key wins over user.signingkey
+    fn from_config(repo: &Repository, identity_key: Option<&str>) -> Result<Option<Signer>, Box<dyn std::error::Error>> {
+        let git_config = repo.config()?;
+        if !git_config.get_bool("commit.gpgsign").unwrap_or(false) {
+            return Ok(None);
+        }
+        Ok(Some(Signer {
+            program: git_config.get_string("gpg.program").unwrap_or_else(|_| "gpg".to_string()),
+            key: identity_key.map(str::to_string).or_else(|| git_config.get_string("user.signingkey").ok()),
+        }))
+    }
+
+    // Detached armored signature of the commit buffer
+    fn sign(&self, content: &[u8]) -> Result<String, Box<dyn std::error::Error>> {
+        let mut command = ExternalCommand::new(&self.program);
+        command.args(["--status-fd=2", "-bsa"]);
+        if let Some(key) = &self.key {
+            command.args(["-u", key]);
+        }
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| t("signing-not-run", &[("program", &self.program), ("error", &e.to_string())]))?;
+        child.stdin.take().ok_or_else(|| t("signing-no-stdin", &[]))?.write_all(content)?;
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(t("signing-failed", &[("error", String::from_utf8_lossy(&output.stderr).trim())]).into());
+        }
+        Ok(String::from_utf8(output.stdout)?)
+    }
+}
+
+// Name of the branch HEAD points to, which may not have a commit yet
+fn head_branch(repo: &Repository) -> Result<String, Box<dyn std::error::Error>> {
+    let head = repo.find_reference("HEAD")?;
+    match head.symbolic_target() {
+        Some(target) => Ok(target.strip_prefix("refs/heads/").unwrap_or(target).to_string()),
+        // Detached, like `git rev-parse --abbrev-ref HEAD`
+        None if head.target().is_some() => Ok("HEAD".to_string()),
+        None => Err(t("no-current-branch", &[]).into()),
+    }
+}
+
+// Move the current branch to the new commit, creating it for the first
+// commit of a new repository
+fn update_head(repo: &Repository, commit_id: Oid, reflog_message: &str) -> Result<(), Box<dyn std::error::Error>> {
+    match repo.head() {
+        Ok(mut head) => {
+            head.set_target(commit_id, reflog_message)?;
+        }
+        Err(e) if e.code() == ErrorCode::UnbornBranch => {
+            let head = repo.find_reference("HEAD")?;
+            let target = head.symbolic_target().ok_or_else(|| t("no-current-branch", &[]))?;
+            repo.reference(target, commit_id, false, reflog_message)?;
+        }
+        Err(e) => return Err(e.into()),
+    }
     Ok(())
-} 
\ No newline at end of file
+}
+
+// Create a commit without moving any reference, signed if a signer is given
+fn write_commit(
+    repo: &Repository,
+    author: &Signature,
+    committer: &Signature,
+    message: &str,
+    tree: &git2::Tree,
+    parents: &[&Commit],
+    signer: Option<&Signer>,
+) -> Result<Oid, Box<dyn std::error::Error>> {
+    let Some(signer) = signer else {
+        return Ok(repo.commit(None, author, committer, message, tree, parents)?);
+    };
+    let buffer = repo.commit_create_buffer(author, committer, message, tree, parents)?;
+    if let Some(content) = buffer.as_str() {
+        let signature = signer.sign(content.as_bytes())?;
+        return Ok(repo.commit_signed(content, signature.trim_end(), None)?);
+    }
+    // A name or email that isn't UTF-8 can't go through commit_signed
+    let split = buffer.windows(2).position(|pair| pair == b"\n\n").ok_or_else(|| t("no-commit-headers-end", &[]))?;
+    write_raw_commit(repo, &buffer[..=split], &buffer[split + 2..], Some(signer))
+}
+
+// libgit2 only writes UTF-8 messages, so a commit in another encoding is put
+// together here: the headers libgit2 creates, then the encoding header and
+// the converted message
+fn write_encoded_commit(
+    repo: &Repository,
+    author: &Signature,
+    committer: &Signature,
+    encoded: &EncodedMessage,
+    tree: &git2::Tree,
+    parents: &[&Commit],
+    signer: Option<&Signer>,
+) -> Result<Oid, Box<dyn std::error::Error>> {
+    // With an empty message the buffer is only the headers and a blank line
+    let buffer = repo.commit_create_buffer(author, committer, "", tree, parents)?;
+    let mut headers = buffer.strip_suffix(b"\n").unwrap_or(&buffer).to_vec();
+    headers.extend_from_slice(format!("encoding {}\n", encoded.encoding).as_bytes());
+    write_raw_commit(repo, &headers, &encoded.bytes, signer)
+}
+
+// Write a commit object from its headers, each ending in a newline, and its
+// message. A signature goes last among the headers, like git does
+fn write_raw_commit(
+    repo: &Repository,
+    headers: &[u8],
+    message: &[u8],
+    signer: Option<&Signer>,
+) -> Result<Oid, Box<dyn std::error::Error>> {
+    let object = |headers: &[u8]| [headers, b"\n", message].concat();
+    let mut content = object(headers);
+    if let Some(signer) = signer {
+        let signature = signer.sign(&content)?;
+        let signature = signature.trim_end().replace('\n', "\n ");
+        content = object(&[headers, format!("gpgsig {}\n", signature).as_bytes()].concat());
+    }
+    Ok(repo.odb()?.write(ObjectType::Commit, &content)?)
+}
+
+// The configured identity, at the given date if one was passed
+fn dated_signature(sig: &Signature, date: Option<&str>) -> Result<Signature<'static>, Box<dyn std::error::Error>> {
+    // Keep the signature's bytes when there is nothing to change
+    let Some(date) = date else {
+        return Ok(sig.to_owned());
+    };
+    // libgit2 only builds signatures from UTF-8, so a date can't be set on
+    // one whose bytes aren't
+    let unsupported = |field: &str| t("date-needs-utf8", &[("field", field)]);
+    let name = sig.name().ok_or_else(|| unsupported("user.name"))?;
+    let email = sig.email().ok_or_else(|| unsupported("user.email"))?;
+    Ok(Signature::new(name, email, &parse_date(date)?)?)
+}
+
+// Best effort check whether `oid` is already reachable from the branch's upstream
+fn pushed_to(repo: &Repository, branch: &str, oid: Oid) -> Option<String> {
+    let upstream = repo.find_branch(branch, BranchType::Local).ok()?.upstream().ok()?;
+    let upstream_oid = upstream.get().target()?;
+    let pushed = upstream_oid == oid || repo.graph_descendant_of(upstream_oid, oid).unwrap_or(false);
+    if pushed {
+        Some(upstream.name().ok().flatten().unwrap_or("upstream").to_string())
+    } else {
+        None
+    }
+}
+
+// Best effort reconstruction of the message from the slugified last branch segment
+fn message_from_branch(branch: &str) -> String {
+    let segment = branch.rsplit('/').next().unwrap_or(branch);
+    segment.replace('-', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_in_answers_are_kept() {
+        let placeholder = Regex::new(r"\{[^{}]*\}").unwrap();
+        let mut asked = Vec::new();
+        let filled = replace_placeholders(&placeholder, "{ticket} for {team}", |name| {
+            asked.push(name.to_string());
+            Ok(format!(" {{{}}} ", if name == "ticket" { "none" } else { "x" }))
+        })
+        .unwrap();
+        assert_eq!(filled, "{none} for {x}");
+        assert_eq!(asked, ["ticket", "team"]);
+    }
+
+    #[cfg(not(windows))]
+    fn sh(command: &str) -> String {
+        let output = ExternalCommand::new("sh").arg("-c").arg(command).env("GWF_TEST_VAR", "from env").output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn expand_command_quotes_placeholders() {
+        assert_eq!(expand_command("echo {subject}", &[("subject", "it's done")]), "echo 'it'\\''s done'");
+        assert_eq!(sh(&expand_command("echo {subject}", &[("subject", "it's done")])), "it's done\n");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn expand_command_keeps_variables_in_values_literal() {
+        let command = expand_command("printf '%s|%s' {subject} \"$GWF_TEST_VAR\"", &[("subject", "fix $HOME and $GWF_TEST_VAR")]);
+        assert_eq!(sh(&command), "fix $HOME and $GWF_TEST_VAR|from env");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn expand_command_expands_placeholders_before_the_shell() {
+        // A placeholder that the shell would otherwise treat as literal text
+        let command = expand_command("echo {branch}-$GWF_TEST_VAR", &[("branch", "feat/x")]);
+        assert_eq!(command, "echo 'feat/x'-$GWF_TEST_VAR");
+        assert_eq!(sh(&command), "feat/x-from env\n");
+    }
+}
@@ -0,0 +1,52 @@
+use git2::Repository;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::error::IoContext;
+
+const HOOK_NAME: &str = "commit-msg";
+// Marks a hook as written by gwf, so reinstalling it can safely replace it
+const HOOK_MARKER: &str = "# Installed by gwf";
+const HOOK_SCRIPT: &str = "#!/bin/sh
+# Installed by gwf: check the message against the conventional commit format
+exec gwf lint \"$1\"
+";
+
+// Write a commit-msg hook running `gwf lint`. A hook gwf didn't write is only
+// replaced with `force`
+pub fn install_hook(force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = Repository::open_from_env()?;
+    let hooks_dir = hooks_dir(&repo)?;
+    let hook = hooks_dir.join(HOOK_NAME);
+
+    if let Ok(existing) = fs::read(&hook) {
+        let ours = String::from_utf8_lossy(&existing).lines().any(|line| line.starts_with(HOOK_MARKER));
+        if !ours && !force {
+            return Err(format!("{} already exists and was not installed by gwf, pass --force to replace it", hook.display()).into());
+        }
+    }
+
+    fs::create_dir_all(&hooks_dir).context("create hooks directory", &hooks_dir)?;
+    fs::write(&hook, HOOK_SCRIPT).context("write commit-msg hook", &hook)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).context("make commit-msg hook executable", &hook)?;
+    }
+    println!("Installed {} hook: {}", HOOK_NAME, hook.display());
+    Ok(())
+}
+
+// Where git looks for hooks: core.hooksPath when set, relative to the working
+// tree like git resolves it, otherwise the hooks directory of the common dir
+fn hooks_dir(repo: &Repository) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let config = repo.config()?;
+    match config.get_path("core.hooksPath") {
+        Ok(path) if path.is_absolute() => Ok(path),
+        Ok(path) => {
+            let base = repo.workdir().unwrap_or(repo.path());
+            Ok(base.join(path))
+        }
+        Err(_) => Ok(repo.commondir().join("hooks")),
+    }
+}
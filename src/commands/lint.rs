@@ -0,0 +1,179 @@
+use git2::Repository;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::config::{load_config, Config, ConfigArgs};
+use crate::conventional::parse_conventional;
+use crate::error::IoContext;
+use crate::message::{long_body_lines, lossy_utf8};
+use crate::signature::{signature, Role};
+
+const HEADER_MAX_LENGTH: usize = 72;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LintViolation {
+    pub rule: &'static str,
+    pub message: String,
+    pub severity: Severity,
+}
+
+#[derive(Debug, Serialize)]
+struct LintReport<'a> {
+    valid: bool,
+    violations: &'a [LintViolation],
+}
+
+// Lint a commit message file (or stdin) against the conventional commit format
+pub fn lint(file: Option<&Path>, json: bool, config_args: &ConfigArgs) -> Result<(), Box<dyn std::error::Error>> {
+    // Linting works outside a repository too, with the default settings
+    let repo = Repository::open_from_env().ok();
+    let config = match &repo {
+        Some(repo) => load_config(repo, config_args)?,
+        None => Config::default(),
+    };
+    // Who will commit the message, for strict_dco
+    let committer_email = repo
+        .as_ref()
+        .and_then(|repo| signature(repo, Role::Committer).ok())
+        .and_then(|sig| sig.email().map(str::to_string));
+
+    let content = match file {
+        Some(file) if file != Path::new("-") => {
+            lossy_utf8(fs::read(file).context("read commit message", file)?, &file.display().to_string())
+        }
+        _ => {
+            let mut bytes = Vec::new();
+            io::stdin().read_to_end(&mut bytes)?;
+            lossy_utf8(bytes, "stdin")
+        }
+    };
+
+    let violations = lint_message(&content, &config, committer_email.as_deref());
+    let valid = !violations.iter().any(|v| v.severity == Severity::Error);
+
+    if json {
+        let report = LintReport { valid, violations: &violations };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for violation in &violations {
+            let severity = match violation.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            eprintln!("{} [{}]: {}", severity, violation.rule, violation.message);
+        }
+        if valid {
+            println!("Commit message is valid");
+        }
+    }
+
+    if valid {
+        Ok(())
+    } else {
+        Err("Commit message does not follow the conventional commit format".into())
+    }
+}
+
+pub fn lint_message(content: &str, config: &Config, committer_email: Option<&str>) -> Vec<LintViolation> {
+    let mut violations = Vec::new();
+
+    // Ignore git's comment lines
+    let lines: Vec<&str> = content.lines().filter(|line| !line.starts_with('#')).collect();
+    let header = lines.first().map(|line| line.trim_end()).unwrap_or_default();
+
+    // Messages generated by git itself are not conventional
+    if header.starts_with("Merge ") || header.starts_with("fixup! ") || header.starts_with("squash! ") {
+        return violations;
+    }
+
+    if header.chars().count() > HEADER_MAX_LENGTH {
+        violations.push(LintViolation {
+            rule: "header-max-length",
+            message: format!("Header is longer than {} characters", HEADER_MAX_LENGTH),
+            severity: Severity::Warning,
+        });
+    }
+
+    if lines.get(1).is_some_and(|line| !line.trim().is_empty()) {
+        violations.push(LintViolation {
+            rule: "body-leading-blank",
+            message: "Body must be separated from the header by a blank line".to_string(),
+            severity: Severity::Warning,
+        });
+    }
+
+    if let Some(width) = config.max_body_line_length {
+        let long_lines = long_body_lines(content, width);
+        if !long_lines.is_empty() {
+            let numbers: Vec<String> = long_lines.iter().map(usize::to_string).collect();
+            violations.push(LintViolation {
+                rule: "body-max-line-length",
+                message: format!("Body lines longer than {} characters: {}", width, numbers.join(", ")),
+                severity: Severity::Warning,
+            });
+        }
+    }
+
+    // Developer Certificate of Origin sign-offs, optionally by the committer
+    if config.require_signoff.unwrap_or(false) || config.strict_dco.unwrap_or(false) {
+        let signoffs: Vec<&str> = lines
+            .iter()
+            .filter_map(|line| line.strip_prefix("Signed-off-by:"))
+            .map(str::trim)
+            .collect();
+        if signoffs.is_empty() {
+            violations.push(LintViolation {
+                rule: "signed-off-by",
+                message: "Message must have a `Signed-off-by: Name <email>` trailer, add one with `finish --signoff`".to_string(),
+                severity: Severity::Error,
+            });
+        } else if let (true, Some(email)) = (config.strict_dco.unwrap_or(false), committer_email) {
+            let signed = signoffs.iter().any(|signoff| signoff.ends_with(&format!("<{}>", email)));
+            if !signed {
+                violations.push(LintViolation {
+                    rule: "signed-off-by-committer",
+                    message: format!("No `Signed-off-by` trailer matches the committer email {}", email),
+                    severity: Severity::Error,
+                });
+            }
+        }
+    }
+
+    let commit = match parse_conventional(header) {
+        Ok(commit) => commit,
+        Err(e) => {
+            violations.push(LintViolation {
+                rule: e.rule(),
+                message: e.to_string(),
+                severity: Severity::Error,
+            });
+            return violations;
+        }
+    };
+
+    // Dependency update tools pick their own type, so those commits aren't held
+    // to the configured list
+    let deps = config
+        .deps_scope
+        .as_ref()
+        .is_some_and(|deps_scope| commit.scope.is_some_and(|scope| scope.eq_ignore_ascii_case(deps_scope)));
+    let types = config.type_names();
+    if commit.type_ != "revert" && !deps && !types.iter().any(|t| t == commit.type_) {
+        violations.push(LintViolation {
+            rule: "type-enum",
+            message: format!("Type `{}` must be one of: {}", commit.type_, types.join(", ")),
+            severity: Severity::Error,
+        });
+    }
+
+    violations
+}
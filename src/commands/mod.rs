@@ -1,2 +1,15 @@
+pub mod branches;
+pub mod bump;
+pub mod changelog;
+pub mod config;
+pub mod doctor;
+pub mod export;
 pub mod nfb;
-pub mod finish; 
\ No newline at end of file
+pub mod finish;
+pub mod hook;
+pub mod lint;
+pub mod note;
+pub mod prune;
+pub mod revert;
+pub mod status;
+pub mod switch;
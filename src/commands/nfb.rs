@@ -1,80 +1,472 @@
-use git2::Repository;
+use git2::{BranchType, CheckoutNotificationType, ErrorCode, Repository, StatusOptions};
 use std::fs;
 use std::io::{self, Write};
-use std::path::PathBuf;
 use slug::slugify;
-use dialoguer::FuzzySelect;
-
-// Common conventional commit types
-const CONVENTIONAL_TYPES: &[&str] = &[
-    "feat",     // New feature
-    "fix",      // Bug fix
-    "docs",     // Documentation changes
-    "style",    // Code style changes (formatting, etc.)
-    "refactor", // Code refactoring
-    "perf",     // Performance improvements
-    "test",     // Adding or modifying tests
-    "build",    // Build system or external dependencies
-    "ci",       // CI configuration changes
-    "chore",    // Other changes that don't modify source or test files
-];
-
-const GWF_DIR: &str = ".gwf";
-
-fn get_gwf_dir() -> PathBuf {
-    dirs::home_dir().unwrap().join(GWF_DIR)
+use dialoguer::{FuzzySelect, Input};
+use regex::Regex;
+
+use crate::config::{load_config, Config, ConfigArgs, TypeEntry};
+use crate::conventional::parse_conventional;
+use crate::history::commits;
+use crate::i18n::t;
+use crate::interrupt;
+use crate::metadata::{metadata_file, prepare_metadata_dir, read_metadata, write_metadata, BranchMetadata};
+use crate::prompt::{can_prompt, confirm, missing};
+use crate::scope::{canonical_scope, infer_scope, scope_from_cwd};
+
+// How many commits back `--reuse` offers headers from
+const RECENT_COMMIT_LIMIT: usize = 100;
+
+// Values given on the command line, anything missing is prompted for
+#[derive(Debug, Default)]
+pub struct NfbOptions {
+    pub type_: Option<String>,
+    pub scope: Option<String>,
+    pub message: Option<String>,
+    pub config_args: ConfigArgs,
+    pub resume: bool,
+    pub no_metadata: bool,
+    pub switch_back_after: bool,
+    pub track: Option<String>,
+    pub assume_yes: bool,
+    pub type_from_message: bool,
+    pub force: bool,
+    pub require_clean: bool,
+    pub dry_run: bool,
+    pub reuse: bool,
+    pub scope_from_cwd: bool,
+}
+
+pub fn nfb(options: &NfbOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = Repository::open_from_env()?;
+    let mut config = load_config(&repo, &options.config_args)?;
+    if options.no_metadata {
+        config.use_metadata = Some(false);
+    }
+
+    let interactive = can_prompt(options.assume_yes);
+    // A picked recent commit fills in whatever wasn't given on the command line
+    let reused = if options.reuse {
+        if !interactive {
+            return Err(t("reuse-needs-terminal", &[]).into());
+        }
+        pick_recent_commit(&repo)?
+    } else {
+        None
+    };
+    let guessed = match (&options.type_, &options.message) {
+        (None, Some(message)) if options.type_from_message => guess_type(&config, message),
+        _ => None,
+    };
+    let guessed = match guessed {
+        Some(type_) if confirm(&t("confirm-guessed-type", &[("type", &type_)]), true, options.assume_yes)? => Some(type_),
+        _ => None,
+    };
+    let reused_type = reused.as_ref().map(|r| r.type_.clone());
+    let type_ = match options.type_.clone().or(guessed).or(reused_type) {
+        Some(type_) => type_,
+        None if interactive => prompt_type(&t("prompt-type", &[]), &config.types(), config.default_type.as_deref()),
+        None => match &config.default_type {
+            Some(default_type) => default_type.clone(),
+            None => return Err(missing(&t("input-type", &[]), "--type")),
+        },
+    };
+    let scope = match options.scope.clone().or(reused.as_ref().map(|r| r.scope.clone())) {
+        Some(scope) => scope,
+        None => {
+            let from_cwd = if options.scope_from_cwd { scope_from_cwd(&repo)? } else { None };
+            let inferred = match from_cwd {
+                Some(scope) => Some(scope),
+                None if config.infer_scope.unwrap_or(false) => infer_scope(&repo)?,
+                None => None,
+            };
+            match inferred {
+                Some(scope) => {
+                    println!("{}", t("inferred-scope", &[("scope", &scope)]));
+                    scope
+                }
+                None if interactive => match &config.scopes {
+                    Some(scopes) if !scopes.is_empty() => prompt_scope(&t("prompt-scope", &[]), scopes),
+                    _ => prompt_user(&t("prompt-scope", &[])),
+                },
+                // The scope is optional, so go without one
+                None => String::new(),
+            }
+        }
+    };
+    // Reuse the existing spelling of a scope that differs only in case
+    let canonical = if config.normalize_scopes.unwrap_or(false) && !scope.is_empty() {
+        canonical_scope(&repo, &scope)?
+    } else {
+        None
+    };
+    let scope = match canonical {
+        Some(canonical) => {
+            let prompt = t("confirm-existing-scope", &[("scope", &scope), ("canonical", &canonical)]);
+            if confirm(&prompt, true, options.assume_yes)? {
+                println!("{}", t("using-existing-scope", &[("scope", &canonical)]));
+                canonical
+            } else {
+                scope
+            }
+        }
+        _ => scope,
+    };
+    let message = match (&options.message, &reused) {
+        (Some(message), _) => message.clone(),
+        (None, Some(reused)) => Input::new()
+            .with_prompt(t("prompt-message", &[]).trim_end().trim_end_matches(':'))
+            .with_initial_text(&reused.subject)
+            .interact_text()?,
+        (None, None) if interactive => {
+            // Show what finish will add to the body, so the message doesn't repeat it
+            if let Some(template) = config.body_template(&repo, &type_)? {
+                println!("{}\n{}", t("body-template", &[("type", &type_)]), template.trim_end());
+            }
+            prompt_user(&t("prompt-message", &[]))
+        }
+        (None, None) => return Err(missing(&t("input-message", &[]), "--message")),
+    };
+
+    if options.require_clean || config.require_clean_tree_for_nfb.unwrap_or(false) {
+        ensure_clean_tree(&repo)?;
+    }
+    // Check the remote before creating anything
+    if let Some(remote) = &options.track {
+        repo.find_remote(remote).map_err(|_| t("remote-missing", &[("remote", remote)]))?;
+    }
+    let branch_name = new_branch(&repo, &config, &type_, &scope, &message, options)?;
+    if let Some(remote) = &options.track {
+        if options.dry_run {
+            println!("{}", t("would-track", &[("remote", remote), ("branch", &branch_name)]));
+        } else {
+            track_remote(&repo, &branch_name, remote)?;
+        }
+    }
+    Ok(())
+}
+
+// A recent conventional commit, split into the parts nfb asks for
+struct RecentCommit {
+    type_: String,
+    scope: String,
+    subject: String,
+}
+
+// Let the user pick one of the recent conventional commit headers, newest
+// first with duplicates dropped. None when there are no such commits
+fn pick_recent_commit(repo: &Repository) -> Result<Option<RecentCommit>, Box<dyn std::error::Error>> {
+    let mut headers: Vec<String> = Vec::new();
+    if let Ok(head) = repo.head().and_then(|head| head.peel_to_commit()) {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(head.id())?;
+        for commit in commits(repo, revwalk).take(RECENT_COMMIT_LIMIT) {
+            let commit = commit?;
+            let header = String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()).into_owned();
+            if parse_conventional(&header).is_ok() && !headers.contains(&header) {
+                headers.push(header);
+            }
+        }
+    }
+    if headers.is_empty() {
+        eprintln!("{}", t("nothing-to-reuse", &[("limit", &RECENT_COMMIT_LIMIT.to_string())]));
+        return Ok(None);
+    }
+
+    let selection = FuzzySelect::new()
+        .with_prompt(t("prompt-reuse", &[]))
+        .items(&headers)
+        .interact()?;
+    let parsed = parse_conventional(&headers[selection])?;
+    Ok(Some(RecentCommit {
+        type_: parsed.type_.to_string(),
+        scope: parsed.scope.unwrap_or_default().to_string(),
+        subject: parsed.subject.to_string(),
+    }))
+}
+
+// The configured type whose keywords appear first in the message, if any
+fn guess_type(config: &Config, message: &str) -> Option<String> {
+    let allowed = config.type_names();
+    let keywords = config.type_keywords();
+    message
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .find_map(|word| {
+            keywords
+                .iter()
+                .filter(|(type_, _)| allowed.contains(type_))
+                .find(|(_, words)| words.iter().any(|w| w.eq_ignore_ascii_case(word)))
+                .map(|(type_, _)| type_.clone())
+        })
+}
+
+// Refuse to start a branch while staged or unstaged changes would be carried
+// over. Untracked files don't count
+fn ensure_clean_tree(repo: &Repository) -> Result<(), Box<dyn std::error::Error>> {
+    let mut status_opts = StatusOptions::new();
+    status_opts.include_untracked(false).exclude_submodules(true);
+    let statuses = repo.statuses(Some(&mut status_opts))?;
+    let dirty: Vec<String> = statuses
+        .iter()
+        .filter(|entry| !entry.status().is_ignored())
+        .map(|entry| String::from_utf8_lossy(entry.path_bytes()).into_owned())
+        .collect();
+    if dirty.is_empty() {
+        return Ok(());
+    }
+    Err(t("dirty-tree", &[("paths", &dirty.join("\n  "))]).into())
 }
 
-pub fn new_branch(type_: &str, scope: &str, message: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let repo = Repository::open(".")?;
+// Point the branch's upstream at <remote>/<branch>, like `git push -u` would.
+// The remote branch doesn't need to exist yet, the first push creates it
+fn track_remote(repo: &Repository, branch_name: &str, remote: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut git_config = repo.config()?;
+    git_config.set_str(&format!("branch.{}.remote", branch_name), remote)?;
+    git_config.set_str(&format!("branch.{}.merge", branch_name), &format!("refs/heads/{}", branch_name))?;
+    println!("{}", t("tracking", &[("remote", remote), ("branch", branch_name)]));
+    Ok(())
+}
+
+// Create the feature branch and record its metadata. With --switch-back-after,
+// only the ref is created and the current checkout is left alone
+pub fn new_branch(
+    repo: &Repository,
+    config: &Config,
+    type_: &str,
+    scope: &str,
+    message: &str,
+    options: &NfbOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let stay = options.switch_back_after;
+    // Only the subject goes into the branch name
+    let subject = message.lines().next().unwrap_or_default();
+    let preserve = match &config.preserve_pattern {
+        Some(pattern) => Some(Regex::new(pattern).map_err(|e| t("invalid-preserve-pattern", &[("error", &e.to_string())]))?),
+        None => None,
+    };
+    let subject = slugify_preserving(subject, preserve.as_ref(), "-");
+    let scope_separator = config.scope_separator.as_deref().unwrap_or("-");
+    if scope_separator.is_empty() || scope_separator.contains(|c: char| c == '/' || c.is_whitespace()) {
+        return Err(t("invalid-scope-separator", &[("separator", &format!("{:?}", scope_separator))]).into());
+    }
     let branch_name = if scope.is_empty() {
-        format!("{}/{}", slugify(type_), slugify(message))
+        format!("{}/{}", slugify(type_), subject)
     } else {
-        format!("{}/{}/{}", slugify(type_), slugify(scope), slugify(message))
+        format!("{}/{}/{}", slugify(type_), slugify_preserving(scope, preserve.as_ref(), scope_separator), subject)
+    };
+
+    // Resuming checks out the existing branch and refreshes its metadata
+    let exists = repo.find_branch(&branch_name, BranchType::Local).is_ok();
+    if exists && !options.resume {
+        return Err(t("branch-exists", &[("branch", &branch_name)]).into());
+    }
+    let types = config.type_names();
+    if !types.iter().any(|t| t == type_) {
+        return Err(t("unknown-type", &[("type", type_), ("types", &types.join(", "))]).into());
+    }
+
+    // Everything above only validates, so a dry run stops here
+    if options.dry_run {
+        let key = if exists { "would-resume-branch" } else { "would-create-branch" };
+        println!("{}", t(key, &[("branch", &branch_name)]));
+        if config.uses_metadata() {
+            println!("{}", t("would-write-metadata", &[("path", &metadata_file(repo, config, &branch_name)?.display().to_string())]));
+        }
+        return Ok(branch_name);
+    }
+
+    // A Ctrl-C waits until the branch and its metadata are both in place or
+    // both rolled back
+    let what = t("switch-to-branch", &[("branch", &branch_name)]);
+    interrupt::deferred(&what, || -> Result<(), Box<dyn std::error::Error>> {
+        // Store the commit message in a file outside the working tree. Type and
+        // scope are kept too, so scopes containing slashes survive the round trip.
+        // It's written before touching any branch, so a failure here (e.g. no
+        // permission in ~/.gwf) leaves nothing behind
+        let written_metadata = if config.uses_metadata() {
+            prepare_metadata_dir(repo, config)?;
+            let metadata_path = metadata_file(repo, config, &branch_name)?;
+            let previous = fs::read(&metadata_path).ok();
+            let metadata = BranchMetadata {
+                type_: Some(slugify(type_)),
+                scope: Some(
+                    scope
+                        .split('/')
+                        .map(|segment| slugify_preserving(segment, preserve.as_ref(), scope_separator))
+                        .collect::<Vec<_>>()
+                        .join("/"),
+                ),
+                message: message.to_string(),
+                // Notes belong to the branch, keep them when resuming it
+                notes: if exists { read_metadata(&metadata_path)?.map(|m| m.notes).unwrap_or_default() } else { Vec::new() },
+            };
+            write_metadata(&metadata_path, &metadata)?;
+            Some((metadata_path, previous))
+        } else {
+            None
+        };
+
+        let switched = if stay {
+            if exists {
+                Ok(())
+            } else {
+                repo.head()
+                    .and_then(|head| head.peel_to_commit())
+                    .and_then(|head| repo.branch(&branch_name, &head, false))
+                    .map(|_| ())
+                    .map_err(Into::into)
+            }
+        } else if exists {
+            checkout_branch(repo, &branch_name, options.force)
+        } else {
+            create_and_checkout(repo, &branch_name, options.force)
+        };
+
+        // Undo the metadata and any new branch, HEAD only moves once the checkout succeeded
+        if let Err(e) = switched {
+            if let Some((metadata_path, previous)) = written_metadata {
+                let restored = match previous {
+                    Some(previous) => fs::write(&metadata_path, previous),
+                    None => fs::remove_file(&metadata_path),
+                };
+                if let Err(restore_error) = restored {
+                    eprintln!("{}", t("metadata-not-restored", &[("path", &metadata_path.display().to_string()), ("error", &restore_error.to_string())]));
+                }
+            }
+            if !exists {
+                if let Ok(mut branch) = repo.find_branch(&branch_name, BranchType::Local) {
+                    if let Err(delete_error) = branch.delete() {
+                        eprintln!("{}", t("branch-not-deleted", &[("branch", &branch_name), ("error", &delete_error.to_string())]));
+                    }
+                }
+            }
+            return Err(e);
+        }
+        Ok(())
+    })?;
+
+    if stay {
+        let current = repo.head()?.shorthand().unwrap_or("HEAD").to_string();
+        let key = if exists { "branch-updated-staying" } else { "branch-created-staying" };
+        println!("{}", t(key, &[("branch", &branch_name), ("current", &current)]));
+    } else if exists {
+        println!("{}", t("branch-resumed", &[("branch", &branch_name)]));
+    } else {
+        println!("{}", t("branch-created", &[("branch", &branch_name)]));
+    }
+    Ok(branch_name)
+}
+
+// Slugify text with words joined by `separator`, keeping the parts matching
+// `preserve` (e.g. ticket IDs) verbatim
+fn slugify_preserving(text: &str, preserve: Option<&Regex>, separator: &str) -> String {
+    let slug = |text: &str| slugify(text).replace('-', separator);
+    let Some(preserve) = preserve else {
+        return slug(text);
     };
+    let mut parts = Vec::new();
+    let mut last = 0;
+    for found in preserve.find_iter(text) {
+        parts.push(slug(&text[last..found.start()]));
+        parts.push(found.as_str().to_string());
+        last = found.end();
+    }
+    parts.push(slug(&text[last..]));
+    parts.retain(|part| !part.is_empty());
+    parts.join(separator)
+}
 
+// Create a branch from the current HEAD commit and check it out
+pub fn create_and_checkout(repo: &Repository, branch_name: &str, force: bool) -> Result<(), Box<dyn std::error::Error>> {
     // Get the current HEAD commit
     let head = repo.head()?;
     let parent = repo.find_commit(head.target().unwrap())?;
 
     // Create the new branch
-    repo.branch(&branch_name, &parent, false)?;
+    repo.branch(branch_name, &parent, false)?;
 
-    // Check out the new branch
+    checkout_branch(repo, branch_name, force)
+}
+
+// Point HEAD at a local branch and update the working tree. A safe checkout
+// refuses to overwrite local changes, `force` discards them instead
+pub fn checkout_branch(repo: &Repository, branch_name: &str, force: bool) -> Result<(), Box<dyn std::error::Error>> {
     let refname = format!("refs/heads/{}", branch_name);
-    repo.set_head(&refname)?;
+    let target = repo.find_reference(&refname)?.peel_to_commit()?;
 
-    let mut checkout_opts = git2::build::CheckoutBuilder::new();
-    checkout_opts
-        .safe() // Use safe checkout instead of force
-        .recreate_missing(true) // Recreate missing files
-        .allow_conflicts(true); // Allow conflicts to be resolved later
+    let mut conflicts = Vec::new();
+    let result = {
+        let mut checkout_opts = git2::build::CheckoutBuilder::new();
+        if force {
+            checkout_opts.force();
+        } else {
+            checkout_opts.safe(); // Use safe checkout instead of force
+        }
+        checkout_opts
+            .recreate_missing(true) // Recreate missing files
+            // Conflicts abort the checkout before anything is written, so
+            // they can be reported instead of silently skipped
+            .notify_on(CheckoutNotificationType::CONFLICT)
+            .notify(|_, path, _, _, _| {
+                if let Some(path) = path {
+                    conflicts.push(path.display().to_string());
+                }
+                true
+            });
 
-    repo.checkout_head(Some(&mut checkout_opts))?;
+        // Update the working tree before moving HEAD so an existing branch's
+        // files are checked out rather than showing up as local changes
+        repo.checkout_tree(target.as_object(), Some(&mut checkout_opts))
+    };
+    match result {
+        Ok(()) => {}
+        Err(e) if e.code() == ErrorCode::Conflict && !conflicts.is_empty() => {
+            let paths = conflicts.join("\n  ");
+            return Err(t("checkout-conflict", &[("branch", branch_name), ("paths", &paths)]).into());
+        }
+        Err(e) => return Err(e.into()),
+    }
+    repo.set_head(&refname)?;
+    Ok(())
+}
 
-    // Store the commit message in a file outside the repository
-    let config_file = get_gwf_dir().join(slugify(&branch_name));
-    fs::create_dir_all(config_file.parent().unwrap())?;
-    let mut file = fs::File::create(config_file)?;
-    writeln!(file, "{}", message)?;
+// Pick a type, showing descriptions next to the names when configured and
+// starting on the default type
+pub fn prompt_type(prompt: &str, types: &[TypeEntry], default: Option<&str>) -> String {
+    let labels: Vec<String> = types.iter().map(TypeEntry::label).collect();
+    let default = default.and_then(|name| types.iter().position(|t| t.name() == name)).unwrap_or(0);
+    let selection = FuzzySelect::new()
+        .with_prompt(prompt)
+        .items(&labels)
+        .default(default)
+        .interact()
+        .unwrap();
+    types[selection].name().to_string()
+}
 
-    println!("Branch created and checked out: {}", branch_name);
-    Ok(())
+// Pick one of the suggested scopes, no scope, or type another one
+fn prompt_scope(prompt: &str, scopes: &[String]) -> String {
+    const NO_SCOPE: &str = "(no scope)";
+    const OTHER: &str = "(other)";
+    let mut items: Vec<&str> = scopes.iter().map(String::as_str).collect();
+    items.extend([NO_SCOPE, OTHER]);
+    let selection = FuzzySelect::new()
+        .with_prompt(prompt.trim_end().trim_end_matches(':'))
+        .items(&items)
+        .interact()
+        .unwrap();
+    match items[selection] {
+        NO_SCOPE => String::new(),
+        OTHER => prompt_user(prompt),
+        scope => scope.to_string(),
+    }
 }
 
 pub fn prompt_user(prompt: &str) -> String {
-    if prompt.contains("type of the commit") {
-        let selection = FuzzySelect::new()
-            .with_prompt(prompt)
-            .items(CONVENTIONAL_TYPES)
-            .interact()
-            .unwrap();
-        CONVENTIONAL_TYPES[selection].to_string()
-    } else {
-        print!("{}", prompt);
-        io::stdout().flush().unwrap();
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        input.trim().to_string()
-    }
+    print!("{}", prompt);
+    io::stdout().flush().unwrap();
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+    input.trim().to_string()
 } 
\ No newline at end of file
@@ -0,0 +1,36 @@
+use git2::Repository;
+
+use crate::config::{load_config, ConfigArgs};
+use crate::metadata::{metadata_file, prepare_metadata_dir, read_metadata, write_metadata};
+
+// Append a note to the current branch's metadata, or drop all of them with
+// `clear`. finish adds the notes to the commit body
+pub fn note(text: Option<&str>, clear: bool, config_args: &ConfigArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = Repository::open_from_env()?;
+    let config = load_config(&repo, config_args)?;
+    if !config.uses_metadata() {
+        return Err("Notes are kept in the branch metadata, which is disabled by use_metadata = false".into());
+    }
+
+    let head = repo.head()?;
+    let branch = head.shorthand().ok_or("Could not get current branch name")?;
+    prepare_metadata_dir(&repo, &config)?;
+    let path = metadata_file(&repo, &config, branch)?;
+    // Without metadata finish falls back to the branch name, which an empty
+    // message keeps doing
+    let mut metadata = read_metadata(&path)?.unwrap_or_default();
+
+    if clear {
+        let cleared = metadata.notes.len();
+        metadata.notes.clear();
+        write_metadata(&path, &metadata)?;
+        println!("Cleared {} note(s) on {}", cleared, branch);
+        return Ok(());
+    }
+
+    let text = text.map(str::trim).filter(|t| !t.is_empty()).ok_or("Note must not be empty")?;
+    metadata.notes.extend(text.lines().map(|line| line.trim_end().to_string()));
+    write_metadata(&path, &metadata)?;
+    println!("Added note to {} ({} in total)", branch, metadata.notes.len());
+    Ok(())
+}
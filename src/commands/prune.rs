@@ -0,0 +1,82 @@
+use git2::{BranchType, Repository};
+use std::collections::HashSet;
+use std::fs;
+use std::time::{Duration, SystemTime};
+
+use crate::config::{load_config, ConfigArgs, MetadataLocation};
+use crate::error::IoContext;
+use crate::metadata::{is_metadata_name, metadata_dir, metadata_name, remove_metadata};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+// Remove metadata for branches that no longer exist, and any older than
+// metadata_max_age_days
+pub fn prune(dry_run: bool, config_args: &ConfigArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = Repository::open_from_env()?;
+    let config = load_config(&repo, config_args)?;
+    let dir = metadata_dir(&repo, &config)?;
+    if !dir.exists() {
+        println!("No metadata to prune in {}", dir.display());
+        return Ok(());
+    }
+
+    // The home directory is shared by every repository, so a branch missing
+    // here may still exist elsewhere. Only the age limit applies to it.
+    let existing_branches = if config.metadata_location.unwrap_or_default() == MetadataLocation::Home {
+        None
+    } else {
+        let mut names = HashSet::new();
+        for branch in repo.branches(Some(BranchType::Local))? {
+            if let Some(name) = branch?.0.name()? {
+                names.insert(metadata_name(name)?);
+            }
+        }
+        Some(names)
+    };
+    // A limit too large to count in seconds never expires anything
+    let max_age = config
+        .metadata_max_age_days
+        .and_then(|days| days.checked_mul(SECONDS_PER_DAY))
+        .map(Duration::from_secs);
+
+    let mut removed = 0;
+    let mut reclaimed = 0;
+    for entry in fs::read_dir(&dir).context("read gwf metadata directory", &dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !metadata.is_file() || !is_metadata_name(&name) {
+            continue;
+        }
+
+        let reason = if existing_branches.as_ref().is_some_and(|names| !names.contains(&name)) {
+            "branch no longer exists"
+        } else if max_age.is_some_and(|max_age| age(&metadata) > max_age) {
+            "older than metadata_max_age_days"
+        } else {
+            continue;
+        };
+
+        if dry_run {
+            println!("Would remove {} ({})", entry.path().display(), reason);
+        } else {
+            remove_metadata(&repo, &config, &entry.path())?;
+            println!("Removed {} ({})", entry.path().display(), reason);
+        }
+        removed += 1;
+        reclaimed += metadata.len();
+    }
+
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    println!("{} {} metadata file(s), {} bytes", verb, removed, reclaimed);
+    Ok(())
+}
+
+// Time since the file was last written, zero for a clock set in the past
+fn age(metadata: &fs::Metadata) -> Duration {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .unwrap_or_default()
+}
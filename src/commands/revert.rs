@@ -0,0 +1,43 @@
+use git2::Repository;
+
+use crate::signature::{signature, Role};
+
+pub fn revert(rev: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = Repository::open_from_env()?;
+    let target = repo.revparse_single(rev)?.peel_to_commit()?;
+
+    // Apply the inverse of the commit to the index and working tree
+    repo.revert(&target, None)?;
+
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        return Err(format!(
+            "Reverting {} produced conflicts. Resolve them and commit manually",
+            target.id()
+        ).into());
+    }
+
+    let tree_id = index.write_tree()?;
+    let tree = repo.find_tree(tree_id)?;
+    let author = signature(&repo, Role::Author)?;
+    let committer = signature(&repo, Role::Committer)?;
+    let head = repo.head()?;
+    let parent = repo.find_commit(head.target().unwrap())?;
+
+    // Conventional revert message referencing the reverted commit
+    let subject = String::from_utf8_lossy(target.summary_bytes().unwrap_or_default());
+    let commit_message = format!("revert: {}\n\nThis reverts commit {}.", subject, target.id());
+
+    let commit_id = repo.commit(
+        Some("HEAD"),
+        &author,
+        &committer,
+        &commit_message,
+        &tree,
+        &[&parent]
+    )?;
+    repo.cleanup_state()?;
+
+    println!("Created commit: {}", commit_id);
+    Ok(())
+}
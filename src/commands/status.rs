@@ -0,0 +1,124 @@
+use git2::{BranchType, Repository, Status, StatusOptions};
+use std::io::{self, Write};
+
+use crate::config::{load_config, ConfigArgs};
+use crate::metadata::{metadata_file, parse_branch, read_metadata};
+
+// Stands in for a missing field in the porcelain format
+const PORCELAIN_NONE: &str = "-";
+
+// Summarize where the current feature branch stands. `porcelain` prints one
+// line for scripts instead, see print_porcelain
+pub fn status(porcelain: bool, nul: bool, config_args: &ConfigArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = Repository::open_from_env()?;
+    let config = load_config(&repo, config_args)?;
+
+    let head = repo.head()?;
+    let branch = head.shorthand().ok_or("Could not get current branch name")?;
+
+    let metadata = if config.uses_metadata() {
+        read_metadata(&metadata_file(&repo, &config, branch)?)?
+    } else {
+        None
+    };
+    let parsed = parse_branch(branch);
+    let type_ = metadata.as_ref().and_then(|m| m.type_.as_deref()).or(parsed.as_ref().map(|b| b.type_));
+    let scope = metadata.as_ref().and_then(|m| m.scope.as_deref()).or(parsed.as_ref().map(|b| b.scope));
+    let scope = scope.filter(|s| !s.is_empty());
+
+    // Upstream and how far the branch has diverged from it
+    let upstream = match repo.find_branch(branch, BranchType::Local).and_then(|b| b.upstream()) {
+        Ok(upstream) => {
+            let name = upstream.name().ok().flatten().unwrap_or("upstream").to_string();
+            let divergence = match (head.target(), upstream.get().target()) {
+                (Some(local), Some(remote)) => Some(repo.graph_ahead_behind(local, remote)?),
+                _ => None,
+            };
+            Some((name, divergence))
+        }
+        Err(_) => None,
+    };
+
+    // Just counts, so it stays fast in big repositories
+    let mut status_opts = StatusOptions::new();
+    status_opts.include_untracked(true).exclude_submodules(true);
+    let (mut staged, mut unstaged, mut untracked) = (0, 0, 0);
+    for entry in repo.statuses(Some(&mut status_opts))?.iter() {
+        let status = entry.status();
+        if status.is_wt_new() {
+            untracked += 1;
+            continue;
+        }
+        if status.intersects(Status::INDEX_NEW | Status::INDEX_MODIFIED | Status::INDEX_DELETED | Status::INDEX_RENAMED | Status::INDEX_TYPECHANGE) {
+            staged += 1;
+        }
+        if status.intersects(Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE) {
+            unstaged += 1;
+        }
+    }
+    let clean = staged + unstaged + untracked == 0;
+
+    if porcelain {
+        let divergence = upstream.as_ref().and_then(|(_, divergence)| *divergence);
+        return print_porcelain(branch, type_, scope, clean, divergence, nul);
+    }
+
+    println!("Branch: {}", branch);
+    match type_ {
+        Some(type_) => println!("Type: {}", type_),
+        None => println!("Type: none, not a gwf feature branch"),
+    }
+    if let Some(scope) = scope {
+        println!("Scope: {}", scope);
+    }
+    match &metadata {
+        Some(metadata) => println!("Message: {}", metadata.message.lines().next().unwrap_or_default()),
+        None => println!("Message: not stored, finish derives it from the branch name"),
+    }
+    match &upstream {
+        Some((name, Some((ahead, behind)))) => println!("Upstream: {} ({} ahead, {} behind)", name, ahead, behind),
+        Some((name, None)) => println!("Upstream: {}", name),
+        None => println!("Upstream: none"),
+    }
+    if clean {
+        println!("Working tree: clean");
+    } else {
+        println!("Working tree: {} staged, {} unstaged, {} untracked", staged, unstaged, untracked);
+    }
+    Ok(())
+}
+
+// The stable format for scripts and shell prompts, which only ever gains
+// fields at the end: branch, type, scope, clean or dirty, ahead and behind,
+// separated by tabs. Missing values are "-". With `nul` the fields are
+// separated and terminated by NUL instead
+fn print_porcelain(
+    branch: &str,
+    type_: Option<&str>,
+    scope: Option<&str>,
+    clean: bool,
+    divergence: Option<(usize, usize)>,
+    nul: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (ahead, behind) = match divergence {
+        Some((ahead, behind)) => (ahead.to_string(), behind.to_string()),
+        None => (PORCELAIN_NONE.to_string(), PORCELAIN_NONE.to_string()),
+    };
+    let fields = [
+        branch,
+        type_.unwrap_or(PORCELAIN_NONE),
+        scope.unwrap_or(PORCELAIN_NONE),
+        if clean { "clean" } else { "dirty" },
+        &ahead,
+        &behind,
+    ];
+    let mut out = io::stdout().lock();
+    if nul {
+        for field in fields {
+            write!(out, "{}\0", field)?;
+        }
+    } else {
+        writeln!(out, "{}", fields.join("\t"))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use dialoguer::FuzzySelect;
+use git2::{BranchType, Repository};
+
+use crate::commands::nfb::checkout_branch;
+use crate::metadata::parse_branch;
+use crate::prompt::can_prompt;
+
+struct Candidate {
+    label: String,
+    local_name: String,
+    // Remote-tracking branch to create the local branch from
+    remote: Option<String>,
+}
+
+// Pick a gwf feature branch and check it out. With `remote`, branches from
+// remotes are offered too and get a local tracking branch on checkout.
+pub fn switch(remote: bool, assume_yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = Repository::open_from_env()?;
+    let mut candidates = Vec::new();
+
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        let Some(name) = branch.name()? else { continue };
+        if let Some(label) = describe(name) {
+            candidates.push(Candidate { label, local_name: name.to_string(), remote: None });
+        }
+    }
+
+    if remote {
+        for branch in repo.branches(Some(BranchType::Remote))? {
+            let (branch, _) = branch?;
+            let Some(name) = branch.name()? else { continue };
+            // Strip the remote name: origin/feat/api/x -> feat/api/x
+            let Some((_, local_name)) = name.split_once('/') else { continue };
+            if candidates.iter().any(|c| c.local_name == local_name) {
+                continue;
+            }
+            if let Some(label) = describe(local_name) {
+                candidates.push(Candidate {
+                    label: format!("{} [{}]", label, name),
+                    local_name: local_name.to_string(),
+                    remote: Some(name.to_string()),
+                });
+            }
+        }
+    }
+
+    if candidates.is_empty() {
+        return Err("No feature branches found".into());
+    }
+
+    if !can_prompt(assume_yes) {
+        return Err("switch needs a terminal to pick a branch, use `git switch <branch>` in scripts".into());
+    }
+    let labels: Vec<&str> = candidates.iter().map(|c| c.label.as_str()).collect();
+    let selection = FuzzySelect::new()
+        .with_prompt("Switch to branch")
+        .items(&labels)
+        .interact()?;
+    let candidate = &candidates[selection];
+
+    if let Some(remote_name) = &candidate.remote {
+        let remote_branch = repo.find_branch(remote_name, BranchType::Remote)?;
+        let commit = remote_branch.get().peel_to_commit()?;
+        let mut local = repo.branch(&candidate.local_name, &commit, false)?;
+        local.set_upstream(Some(remote_name))?;
+        println!("Created {} tracking {}", candidate.local_name, remote_name);
+    }
+
+    checkout_branch(&repo, &candidate.local_name, false)?;
+    println!("Switched to branch: {}", candidate.local_name);
+    Ok(())
+}
+
+// "type(scope): message  (branch)" for branches following the gwf naming
+fn describe(branch: &str) -> Option<String> {
+    let parsed = parse_branch(branch)?;
+    let header = if parsed.scope.is_empty() {
+        format!("{}: {}", parsed.type_, parsed.message)
+    } else {
+        format!("{}({}): {}", parsed.type_, parsed.scope, parsed.message)
+    };
+    Some(format!("{}  ({})", header, branch))
+}
@@ -0,0 +1,384 @@
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::IoContext;
+use crate::i18n::t;
+use crate::message::lossy_utf8;
+
+const GWF_DIR: &str = ".gwf";
+const GWF_CONFIG: &str = "gwf.toml";
+const GWF_RC: &str = ".gwfrc";
+// Keys a .gwfrc may set, with the config field each one fills
+const GWF_RC_KEYS: &[(&str, &str)] = &[("type", "default_type"), ("scopes", "scopes"), ("types", "types")];
+const GWF_DIR_ENV: &str = "GWF_DIR";
+const GWF_PROFILE_ENV: &str = "GWF_PROFILE";
+
+// The spellings Conventional Commits accepts for the breaking change footer,
+// the first is the default
+const BREAKING_CHANGE_TOKENS: &[&str] = &["BREAKING CHANGE", "BREAKING-CHANGE"];
+
+// Branches `finish` refuses to commit on unless forced
+const DEFAULT_PROTECTED_BRANCHES: &[&str] = &["main", "master", "develop"];
+
+// Common conventional commit types
+const CONVENTIONAL_TYPES: &[&str] = &[
+    "feat",     // New feature
+    "fix",      // Bug fix
+    "docs",     // Documentation changes
+    "style",    // Code style changes (formatting, etc.)
+    "refactor", // Code refactoring
+    "perf",     // Performance improvements
+    "test",     // Adding or modifying tests
+    "build",    // Build system or external dependencies
+    "ci",       // CI configuration changes
+    "chore",    // Other changes that don't modify source or test files
+];
+
+// Words in a message that suggest its commit type, for `nfb --type-from-message`
+const DEFAULT_TYPE_KEYWORDS: &[(&str, &[&str])] = &[
+    ("fix", &["fix", "fixes", "bug", "bugfix", "crash", "broken", "regression"]),
+    ("feat", &["add", "adds", "implement", "implements", "introduce", "support"]),
+    ("docs", &["doc", "docs", "document", "readme", "typo"]),
+    ("refactor", &["refactor", "rename", "restructure", "simplify", "extract"]),
+    ("perf", &["perf", "speed", "faster", "optimize", "optimise"]),
+    ("test", &["test", "tests", "coverage"]),
+    ("build", &["build", "bump", "dependency", "dependencies", "upgrade"]),
+    ("ci", &["ci", "pipeline", "workflow"]),
+    ("style", &["format", "formatting", "lint", "whitespace"]),
+    ("chore", &["chore", "cleanup", "remove"]),
+];
+
+// A configured commit type, either a plain name or a name with a description
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TypeEntry {
+    Name(String),
+    Described { name: String, description: String },
+}
+
+impl TypeEntry {
+    pub fn name(&self) -> &str {
+        match self {
+            TypeEntry::Name(name) | TypeEntry::Described { name, .. } => name,
+        }
+    }
+
+    // What the type prompt shows
+    pub fn label(&self) -> String {
+        match self {
+            TypeEntry::Name(name) => name.clone(),
+            TypeEntry::Described { name, description } => format!("{} — {}", name, description),
+        }
+    }
+}
+
+// Where branch metadata (the stored commit message) lives
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MetadataLocation {
+    // ~/.gwf
+    #[default]
+    Home,
+    // .git/gwf, local to the clone
+    Git,
+    // .gwf in the working tree, excluded from git
+    RepoLocal,
+}
+
+// A named committer identity selected with `finish --identity`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Identity {
+    pub name: String,
+    pub email: String,
+    pub signingkey: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    pub types: Option<Vec<TypeEntry>>,
+    pub post_commit_command: Option<String>,
+    pub post_commit_optional: Option<bool>,
+    pub protected_branches: Option<Vec<String>>,
+    pub metadata_location: Option<MetadataLocation>,
+    pub infer_scope: Option<bool>,
+    pub autowrap_body: Option<usize>,
+    pub base_branch: Option<String>,
+    pub expected_author_domain: Option<String>,
+    pub body_template_file: Option<PathBuf>,
+    pub ticket_pattern: Option<String>,
+    pub ticket_footer: Option<String>,
+    pub use_metadata: Option<bool>,
+    pub identities: Option<BTreeMap<String, Identity>>,
+    pub trailers: Option<Vec<String>>,
+    pub message_prefix: Option<String>,
+    pub message_suffix: Option<String>,
+    pub preserve_pattern: Option<String>,
+    pub run_pre_commit: Option<bool>,
+    pub scope_separator: Option<String>,
+    pub max_body_line_length: Option<usize>,
+    pub rollback_on_post_commit_failure: Option<bool>,
+    pub normalize_scopes: Option<bool>,
+    pub type_keywords: Option<BTreeMap<String, Vec<String>>>,
+    pub metadata_max_age_days: Option<u64>,
+    pub default_type: Option<String>,
+    pub scopes: Option<Vec<String>>,
+    pub deps_scope: Option<String>,
+    pub require_clean_tree_for_nfb: Option<bool>,
+    pub post_commit_by_type: Option<BTreeMap<String, String>>,
+    pub post_commit_by_scope: Option<BTreeMap<String, String>>,
+    pub require_signoff: Option<bool>,
+    pub strict_dco: Option<bool>,
+    pub templates_by_type: Option<BTreeMap<String, String>>,
+    pub breaking_change_token: Option<String>,
+    pub header_template: Option<String>,
+}
+
+impl Config {
+    // Allowed commit types, the conventional ones unless configured
+    pub fn types(&self) -> Vec<TypeEntry> {
+        match &self.types {
+            Some(types) => types.clone(),
+            None => CONVENTIONAL_TYPES.iter().map(|t| TypeEntry::Name(t.to_string())).collect(),
+        }
+    }
+
+    pub fn type_names(&self) -> Vec<String> {
+        self.types().iter().map(|t| t.name().to_string()).collect()
+    }
+
+    // Whether nfb stores branch metadata for finish to read
+    pub fn uses_metadata(&self) -> bool {
+        self.use_metadata.unwrap_or(true)
+    }
+
+    // Footer key finish writes for --breaking
+    pub fn breaking_change_token(&self) -> Result<&str, Box<dyn std::error::Error>> {
+        match self.breaking_change_token.as_deref() {
+            None => Ok(BREAKING_CHANGE_TOKENS[0]),
+            Some(token) if BREAKING_CHANGE_TOKENS.contains(&token) => Ok(token),
+            Some(token) => Err(t("invalid-breaking-token", &[("token", token), ("tokens", &BREAKING_CHANGE_TOKENS.join(", "))]).into()),
+        }
+    }
+
+    // Keywords per commit type, the built-in table unless configured
+    pub fn type_keywords(&self) -> BTreeMap<String, Vec<String>> {
+        match &self.type_keywords {
+            Some(keywords) => keywords.clone(),
+            None => DEFAULT_TYPE_KEYWORDS
+                .iter()
+                .map(|(type_, words)| (type_.to_string(), words.iter().map(|w| w.to_string()).collect()))
+                .collect(),
+        }
+    }
+
+    // The post-commit command for a commit: one configured for its scope wins
+    // over one for its type, which wins over post_commit_command
+    pub fn post_commit_command_for(&self, type_: Option<&str>, scope: Option<&str>) -> Option<&str> {
+        fn lookup<'a>(commands: &'a Option<BTreeMap<String, String>>, key: Option<&str>) -> Option<&'a String> {
+            commands.as_ref().zip(key).and_then(|(commands, key)| commands.get(key))
+        }
+        lookup(&self.post_commit_by_scope, scope)
+            .or_else(|| lookup(&self.post_commit_by_type, type_))
+            .or(self.post_commit_command.as_ref())
+            .map(String::as_str)
+    }
+
+    // Body template for a commit type: its entry in templates_by_type, else
+    // the contents of body_template_file, relative to the repository root
+    pub fn body_template(&self, repo: &Repository, type_: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        if let Some(template) = self.templates_by_type.as_ref().and_then(|templates| templates.get(type_)) {
+            return Ok(Some(template.clone()));
+        }
+        let Some(template_file) = &self.body_template_file else {
+            return Ok(None);
+        };
+        let repo_root = repo.workdir().ok_or("Could not get repository root")?;
+        let template_path = repo_root.join(template_file);
+        let template = fs::read(&template_path).context("read body template", &template_path)?;
+        Ok(Some(lossy_utf8(template, &template_path.display().to_string())))
+    }
+
+    pub fn identity(&self, name: &str) -> Result<&Identity, Box<dyn std::error::Error>> {
+        let identities = self.identities.as_ref();
+        if let Some(identity) = identities.and_then(|identities| identities.get(name)) {
+            return Ok(identity);
+        }
+        let available: Vec<&str> = identities.into_iter().flat_map(|i| i.keys()).map(String::as_str).collect();
+        let available = if available.is_empty() { t("none", &[]) } else { available.join(", ") };
+        Err(t("unknown-identity", &[("name", name), ("available", &available)]).into())
+    }
+
+    pub fn is_protected(&self, branch: &str) -> bool {
+        match &self.protected_branches {
+            Some(branches) => branches.iter().any(|b| b == branch),
+            None => DEFAULT_PROTECTED_BRANCHES.contains(&branch),
+        }
+    }
+}
+
+// The .gwf directory in the home directory, or GWF_DIR when set
+pub fn get_gwf_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if let Some(dir) = env::var_os(GWF_DIR_ENV) {
+        return Ok(PathBuf::from(dir));
+    }
+    let home = dirs::home_dir().ok_or(
+        "Could not determine the home directory. Set GWF_DIR to the directory gwf should use for its files",
+    )?;
+    Ok(home.join(GWF_DIR))
+}
+
+// Config selection given on the command line
+#[derive(Debug, Default, Clone)]
+pub struct ConfigArgs {
+    pub path: Option<PathBuf>,
+    pub profile: Option<String>,
+}
+
+// Read the config from `args.path` if given, otherwise from the repository
+// root, then from the .gwf directory. A selected profile from the home config
+// (or the explicit file) is laid over the base settings. The shared .gwfrc
+// conventions in the repository root come below all of these.
+pub fn load_config(repo: &Repository, args: &ConfigArgs) -> Result<Config, Box<dyn std::error::Error>> {
+    Ok(load_config_with_sources(repo, args)?.0)
+}
+
+// Like load_config, also returning where each top-level setting came from
+pub fn load_config_with_sources(
+    repo: &Repository,
+    args: &ConfigArgs,
+) -> Result<(Config, BTreeMap<String, String>), Box<dyn std::error::Error>> {
+    let mut sources = BTreeMap::new();
+    let mut table = read_rc_table(repo, &mut sources)?;
+    if let Some(config_file) = config_file(repo, args)? {
+        let overrides = read_config_table(&config_file)?;
+        for key in overrides.keys() {
+            sources.insert(key.clone(), config_file.display().to_string());
+        }
+        table.extend(overrides);
+    }
+
+    let profile = args.profile.clone().or_else(|| env::var(GWF_PROFILE_ENV).ok());
+    if let Some(profile) = profile {
+        let profiles_file = match &args.path {
+            Some(config_path) => config_path.clone(),
+            None => get_gwf_dir()?.join(GWF_CONFIG),
+        };
+        let profiles = if profiles_file.exists() {
+            read_config_table(&profiles_file)?.remove("profiles")
+        } else {
+            None
+        };
+        let mut profiles = match profiles {
+            Some(toml::Value::Table(profiles)) => profiles,
+            _ => toml::Table::new(),
+        };
+        match profiles.remove(&profile) {
+            Some(toml::Value::Table(overrides)) => {
+                for key in overrides.keys() {
+                    sources.insert(key.clone(), format!("profile '{}' in {}", profile, profiles_file.display()));
+                }
+                table.extend(overrides);
+            }
+            _ => {
+                let available: Vec<&str> = profiles.keys().map(String::as_str).collect();
+                let available = if available.is_empty() { t("none", &[]) } else { available.join(", ") };
+                return Err(t("unknown-profile", &[("profile", &profile), ("available", &available)]).into());
+            }
+        }
+    }
+
+    table.remove("profiles");
+    sources.remove("profiles");
+    let config = table
+        .try_into()
+        .map_err(|e| t("invalid-config", &[("error", &e.to_string())]))?;
+    Ok((config, sources))
+}
+
+// The config file load_config reads, None when there is none
+pub fn config_file(repo: &Repository, args: &ConfigArgs) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    if let Some(config_path) = &args.path {
+        if !config_path.exists() {
+            return Err(t("config-file-missing", &[("path", &config_path.display().to_string())]).into());
+        }
+        return Ok(Some(config_path.clone()));
+    }
+
+    let repo_root = repo.workdir().ok_or("Could not get repository root")?;
+    let config_file = repo_root.join(GWF_CONFIG);
+    let config_file = if config_file.exists() {
+        config_file
+    } else {
+        get_gwf_dir()?.join(GWF_CONFIG)
+    };
+    Ok(config_file.exists().then_some(config_file))
+}
+
+// The repository's .gwfrc with its keys renamed to config fields, empty when
+// there is none
+fn read_rc_table(repo: &Repository, sources: &mut BTreeMap<String, String>) -> Result<toml::Table, Box<dyn std::error::Error>> {
+    let Some(rc_file) = repo.workdir().map(|workdir| workdir.join(GWF_RC)).filter(|path| path.exists()) else {
+        return Ok(toml::Table::new());
+    };
+    let mut table = toml::Table::new();
+    for (key, value) in read_config_table(&rc_file)? {
+        let field = GWF_RC_KEYS.iter().find(|(rc_key, _)| *rc_key == key).map(|(_, field)| *field).ok_or_else(|| {
+            let keys: Vec<&str> = GWF_RC_KEYS.iter().map(|(rc_key, _)| *rc_key).collect();
+            format!("Unsupported key '{}' in {}, expected one of: {}", key, rc_file.display(), keys.join(", "))
+        })?;
+        sources.insert(field.to_string(), rc_file.display().to_string());
+        table.insert(field.to_string(), value);
+    }
+    Ok(table)
+}
+
+fn read_config_table(config_file: &Path) -> Result<toml::Table, Box<dyn std::error::Error>> {
+    let config_content = fs::read_to_string(config_file).context("read config file", config_file)?;
+    toml::from_str(&config_content)
+        .map_err(|e| format!("Invalid config file {}: {}", config_file.display(), e).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(toml: &str) -> Config {
+        toml::from_str(toml).unwrap()
+    }
+
+    const ALL_LEVELS: &str = r#"
+        post_commit_command = "global"
+        post_commit_by_type = { feat = "feat command", docs = "docs command" }
+        post_commit_by_scope = { api = "api command" }
+    "#;
+
+    #[test]
+    fn scope_command_wins_over_type_and_global() {
+        assert_eq!(config(ALL_LEVELS).post_commit_command_for(Some("feat"), Some("api")), Some("api command"));
+    }
+
+    #[test]
+    fn type_command_wins_over_global() {
+        let config = config(ALL_LEVELS);
+        assert_eq!(config.post_commit_command_for(Some("feat"), Some("ui")), Some("feat command"));
+        assert_eq!(config.post_commit_command_for(Some("docs"), None), Some("docs command"));
+    }
+
+    #[test]
+    fn global_command_is_the_fallback() {
+        let config = config(ALL_LEVELS);
+        assert_eq!(config.post_commit_command_for(Some("fix"), Some("ui")), Some("global"));
+        assert_eq!(config.post_commit_command_for(None, None), Some("global"));
+    }
+
+    #[test]
+    fn type_only_match_without_a_global_command() {
+        let config = config(r#"post_commit_by_type = { docs = "make docs" }"#);
+        assert_eq!(config.post_commit_command_for(Some("docs"), Some("readme")), Some("make docs"));
+        assert_eq!(config.post_commit_command_for(Some("feat"), Some("readme")), None);
+    }
+}
@@ -0,0 +1,129 @@
+use std::error::Error;
+use std::fmt;
+
+// The parts of a conventional commit header: type(scope)!: subject
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConventionalCommit<'a> {
+    pub type_: &'a str,
+    pub scope: Option<&'a str>,
+    pub breaking: bool,
+    pub subject: &'a str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParseError {
+    MissingSeparator,
+    EmptyType,
+    InvalidScope,
+    EmptySubject,
+}
+
+impl ParseError {
+    // Name of the lint rule the error corresponds to
+    pub fn rule(&self) -> &'static str {
+        match self {
+            ParseError::MissingSeparator | ParseError::EmptyType => "header-format",
+            ParseError::InvalidScope => "scope-format",
+            ParseError::EmptySubject => "subject-empty",
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            ParseError::MissingSeparator => "Header must look like `type(scope): subject`",
+            ParseError::EmptyType => "Type must not be empty",
+            ParseError::InvalidScope => "Scope must be a non-empty value in parentheses",
+            ParseError::EmptySubject => "Subject must not be empty",
+        };
+        f.write_str(message)
+    }
+}
+
+impl Error for ParseError {}
+
+pub fn parse_conventional(header: &str) -> Result<ConventionalCommit<'_>, ParseError> {
+    let (prefix, subject) = match header.split_once(": ") {
+        Some(parts) => parts,
+        // "type:" with nothing after it
+        None => (header.trim_end().strip_suffix(':').ok_or(ParseError::MissingSeparator)?, ""),
+    };
+
+    let (prefix, breaking) = match prefix.strip_suffix('!') {
+        Some(prefix) => (prefix, true),
+        None => (prefix, false),
+    };
+    let (type_, scope) = match prefix.split_once('(') {
+        Some((type_, scope)) => {
+            let scope = scope.strip_suffix(')').filter(|s| !s.is_empty() && !s.contains(['(', ')']));
+            (type_, Some(scope.ok_or(ParseError::InvalidScope)?))
+        }
+        None => (prefix, None),
+    };
+    if type_.is_empty() || type_.contains(char::is_whitespace) {
+        return Err(ParseError::EmptyType);
+    }
+
+    let subject = subject.trim();
+    if subject.is_empty() {
+        return Err(ParseError::EmptySubject);
+    }
+
+    Ok(ConventionalCommit { type_, scope, breaking, subject })
+}
+
+// Whether the body of a commit message has a BREAKING CHANGE footer. The
+// header is skipped, "BREAKING CHANGE: x" there is just a subject
+pub fn has_breaking_footer(message: &str) -> bool {
+    message.lines().skip(1).any(|line| line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:"))
+}
+
+// Parse the header of a full commit message. The commit is breaking when the
+// header has a `!` or the body has a BREAKING CHANGE footer
+pub fn parse_commit_message(message: &str) -> Result<ConventionalCommit<'_>, ParseError> {
+    let mut commit = parse_conventional(message.lines().next().unwrap_or_default())?;
+    commit.breaking |= has_breaking_footer(message);
+    Ok(commit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bang_marks_the_header_breaking() {
+        let commit = parse_commit_message("feat!: drop v1").unwrap();
+        assert_eq!(commit, ConventionalCommit { type_: "feat", scope: None, breaking: true, subject: "drop v1" });
+    }
+
+    #[test]
+    fn bang_after_the_scope_marks_the_header_breaking() {
+        let commit = parse_commit_message("feat(api)!: drop v1").unwrap();
+        assert_eq!(commit, ConventionalCommit { type_: "feat", scope: Some("api"), breaking: true, subject: "drop v1" });
+    }
+
+    #[test]
+    fn breaking_change_footer_marks_the_commit_breaking() {
+        let commit = parse_commit_message("feat(api): drop v1\n\nBody.\n\nBREAKING CHANGE: v1 is gone").unwrap();
+        assert!(commit.breaking);
+        assert_eq!(commit.scope, Some("api"));
+    }
+
+    #[test]
+    fn hyphenated_breaking_change_footer_marks_the_commit_breaking() {
+        assert!(parse_commit_message("feat: drop v1\n\nBREAKING-CHANGE: v1 is gone").unwrap().breaking);
+    }
+
+    #[test]
+    fn plain_commit_is_not_breaking() {
+        assert!(!parse_commit_message("feat: add v2\n\nMentions BREAKING CHANGE: mid-line").unwrap().breaking);
+    }
+
+    #[test]
+    fn breaking_change_in_the_header_is_not_a_footer() {
+        assert!(!has_breaking_footer("BREAKING CHANGE: v1 is gone"));
+        assert!(!has_breaking_footer("BREAKING-CHANGE: v1 is gone\n"));
+        assert!(has_breaking_footer("feat: x\n\nBREAKING CHANGE: v1 is gone"));
+    }
+}
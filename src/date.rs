@@ -0,0 +1,183 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone};
+use git2::Time;
+
+use crate::i18n::t;
+
+// Formats accepted with an explicit UTC offset
+const OFFSET_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S %z",
+    "%Y-%m-%d %H:%M %z",
+    "%Y-%m-%dT%H:%M:%S%z",
+    "%a %b %e %H:%M:%S %Y %z",
+];
+
+// Formats interpreted in the local timezone
+const LOCAL_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"];
+
+// Parse a date given on the command line. Accepts RFC 3339, RFC 2822, git's
+// raw "<seconds> <offset>" or "@<seconds>", and the ISO-like formats git
+// prints, e.g. "2024-01-31 14:00:00 +0100" or just "2024-01-31".
+pub fn parse_date(input: &str) -> Result<Time, Box<dyn std::error::Error>> {
+    let input = input.trim();
+
+    if let Some(time) = parse_raw(input) {
+        return Ok(time);
+    }
+    if let Ok(date) = DateTime::parse_from_rfc3339(input).or_else(|_| DateTime::parse_from_rfc2822(input)) {
+        return Ok(to_time(date));
+    }
+    for format in OFFSET_FORMATS {
+        if let Ok(date) = DateTime::parse_from_str(input, format) {
+            return Ok(to_time(date));
+        }
+    }
+    let naive = LOCAL_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+        .or_else(|| NaiveDate::parse_from_str(input, "%Y-%m-%d").ok().and_then(|d| d.and_hms_opt(0, 0, 0)));
+    if let Some(naive) = naive {
+        if let Some(date) = Local.from_local_datetime(&naive).earliest() {
+            return Ok(to_time(date.fixed_offset()));
+        }
+    }
+
+    Err(t("invalid-date", &[("date", input)]).into())
+}
+
+// The end of a range given with `--until`, as the first second after it. A
+// plain date like "2024-03-01" covers that whole day, so the bound is the
+// start of the next one
+pub fn parse_until(input: &str) -> Result<i64, Box<dyn std::error::Error>> {
+    let next_day = NaiveDate::parse_from_str(input.trim(), "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.succ_opt())
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .and_then(|naive| Local.from_local_datetime(&naive).earliest());
+    match next_day {
+        Some(next_day) => Ok(next_day.timestamp()),
+        None => Ok(parse_date(input)?.seconds() + 1),
+    }
+}
+
+// "@1706706000", "1706706000 +0100" or "@1706706000 +0100"
+fn parse_raw(input: &str) -> Option<Time> {
+    let (seconds, offset) = match input.split_once(' ') {
+        Some((seconds, offset)) => (seconds, Some(offset.trim())),
+        None => (input, None),
+    };
+    let is_raw = seconds.starts_with('@') || offset.is_some();
+    let seconds: i64 = seconds.strip_prefix('@').unwrap_or(seconds).parse().ok()?;
+    if !is_raw {
+        return None;
+    }
+    let offset_minutes = match offset {
+        Some(offset) => parse_offset(offset)?,
+        None => 0,
+    };
+    Some(Time::new(seconds, offset_minutes))
+}
+
+// "+0100" or "-05:30" as minutes east of UTC
+fn parse_offset(offset: &str) -> Option<i32> {
+    let (sign, digits) = match offset.as_bytes().first()? {
+        b'+' => (1, &offset[1..]),
+        b'-' => (-1, &offset[1..]),
+        _ => return None,
+    };
+    let digits = digits.replace(':', "");
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits[2..].parse().ok()?;
+    Some(sign * (hours * 60 + minutes))
+}
+
+fn to_time(date: DateTime<FixedOffset>) -> Time {
+    Time::new(date.timestamp(), date.offset().local_minus_utc() / 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(input: &str) -> (i64, i32) {
+        let time = parse_date(input).unwrap();
+        (time.seconds(), time.offset_minutes())
+    }
+
+    // The same wall-clock time in the local timezone, however the tests run
+    fn local(year: i32, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> i64 {
+        let naive = NaiveDate::from_ymd_opt(year, month, day).unwrap().and_hms_opt(hour, minute, second).unwrap();
+        Local.from_local_datetime(&naive).earliest().unwrap().timestamp()
+    }
+
+    #[test]
+    fn rfc_3339() {
+        assert_eq!(utc("2024-01-31T14:00:00+01:00"), (1706706000, 60));
+        assert_eq!(utc("2024-01-31T13:00:00Z"), (1706706000, 0));
+    }
+
+    #[test]
+    fn rfc_2822() {
+        assert_eq!(utc("Wed, 31 Jan 2024 14:00:00 +0100"), (1706706000, 60));
+    }
+
+    #[test]
+    fn git_raw() {
+        assert_eq!(utc("@1706706000 +0100"), (1706706000, 60));
+        assert_eq!(utc("1706706000 -0530"), (1706706000, -330));
+        assert_eq!(utc("@1706706000"), (1706706000, 0));
+        assert_eq!(utc("  @1706706000 -05:30  "), (1706706000, -330));
+    }
+
+    #[test]
+    fn git_iso_with_offset() {
+        assert_eq!(utc("2024-01-31 14:00:00 +0100"), (1706706000, 60));
+        assert_eq!(utc("2024-01-31 14:00 +0100"), (1706706000, 60));
+    }
+
+    #[test]
+    fn date_only_is_local_midnight() {
+        assert_eq!(parse_date("2024-01-31").unwrap().seconds(), local(2024, 1, 31, 0, 0, 0));
+    }
+
+    #[test]
+    fn local_date_and_time() {
+        assert_eq!(parse_date("2024-01-31 14:00").unwrap().seconds(), local(2024, 1, 31, 14, 0, 0));
+        assert_eq!(parse_date("2024-01-31 14:00:30").unwrap().seconds(), local(2024, 1, 31, 14, 0, 30));
+        assert_eq!(parse_date("2024-01-31T14:00:30").unwrap().seconds(), local(2024, 1, 31, 14, 0, 30));
+    }
+
+    #[test]
+    fn invalid_offset() {
+        assert_eq!(parse_offset("+01"), None);
+        assert_eq!(parse_offset("0100"), None);
+        assert_eq!(parse_offset("+01x0"), None);
+        assert_eq!(parse_offset("-05:30"), Some(-330));
+        assert!(parse_raw("1706706000 +01").is_none());
+        assert!(parse_date("1706706000 +01").is_err());
+    }
+
+    #[test]
+    fn unparseable_input_has_a_clear_error() {
+        let error = parse_date("next tuesday").unwrap_err().to_string();
+        assert!(error.contains("'next tuesday'"), "{}", error);
+        assert!(error.contains("\"2024-01-31T14:00:00+01:00\""), "{}", error);
+        // A bare number is not a raw timestamp without `@` or an offset
+        assert!(parse_date("1706706000").is_err());
+    }
+
+    #[test]
+    fn until_a_date_covers_the_whole_day() {
+        let next_day = parse_date("2024-03-02").unwrap().seconds();
+        assert_eq!(parse_until("2024-03-01").unwrap(), next_day);
+        assert!(parse_date("2024-03-01 23:59:59").unwrap().seconds() < next_day);
+    }
+
+    #[test]
+    fn until_a_time_includes_that_second() {
+        assert_eq!(parse_until("@1709287200").unwrap(), 1709287201);
+        assert_eq!(parse_until("2024-03-01T10:00:00Z").unwrap(), 1709287201);
+    }
+}
@@ -0,0 +1,25 @@
+use git2::{Diff, DiffFormat};
+
+use crate::color::{self, BOLD, CYAN, GREEN, RED};
+
+// Print a diff as a patch, colorizing added and removed lines
+pub fn print_diff(diff: &Diff) -> Result<(), git2::Error> {
+    let color = color::enabled();
+    diff.print(DiffFormat::Patch, |_delta, _hunk, line| {
+        let content = String::from_utf8_lossy(line.content());
+        let (origin, style) = match line.origin() {
+            '+' => ("+", GREEN),
+            '-' => ("-", RED),
+            ' ' => (" ", ""),
+            'F' => ("", BOLD),
+            'H' => ("", CYAN),
+            _ => ("", ""),
+        };
+        if color && !style.is_empty() {
+            println!("{}", color::paint(&format!("{}{}", origin, content.trim_end_matches('\n')), style));
+        } else {
+            print!("{}{}", origin, content);
+        }
+        true
+    })
+}
@@ -0,0 +1,14 @@
+use std::io;
+use std::path::Path;
+
+// Attach what gwf was doing and the path involved to IO errors, so permission
+// and missing-file problems are actionable instead of a bare OS message
+pub trait IoContext<T> {
+    fn context(self, action: &str, path: &Path) -> Result<T, Box<dyn std::error::Error>>;
+}
+
+impl<T> IoContext<T> for io::Result<T> {
+    fn context(self, action: &str, path: &Path) -> Result<T, Box<dyn std::error::Error>> {
+        self.map_err(|e| format!("failed to {} at {}: {}", action, path.display(), e).into())
+    }
+}
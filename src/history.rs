@@ -0,0 +1,22 @@
+use git2::{Commit, ErrorCode, Repository, Revwalk};
+
+// Tell the user that a shallow clone only has part of the history, so results
+// computed from it may be incomplete
+pub fn warn_if_shallow(repo: &Repository, what: &str) {
+    if repo.is_shallow() {
+        eprintln!(
+            "Warning: this is a shallow clone, so {} only sees the fetched history and may be incomplete. Run `git fetch --unshallow` to get all of it",
+            what
+        );
+    }
+}
+
+// The commits of a revwalk. In a shallow clone the walk stops quietly at the
+// first commit missing from the object database instead of failing.
+pub fn commits<'r>(repo: &'r Repository, revwalk: Revwalk<'r>) -> impl Iterator<Item = Result<Commit<'r>, git2::Error>> + 'r {
+    let shallow = repo.is_shallow();
+    revwalk.map_while(move |oid| match oid.and_then(|oid| repo.find_commit(oid)) {
+        Err(e) if shallow && e.code() == ErrorCode::NotFound => None,
+        result => Some(result),
+    })
+}
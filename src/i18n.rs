@@ -0,0 +1,358 @@
+use std::env;
+use std::sync::OnceLock;
+
+use crate::message::fill_placeholders;
+
+const GWF_LANG_ENV: &str = "GWF_LANG";
+
+// Languages with translated strings
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Lang {
+    En,
+    Es,
+}
+
+// User-facing strings of nfb and finish, including the errors they pass on
+// from shared modules. {name} placeholders are filled by `t`
+const EN: &[(&str, &str)] = &[
+    // nfb
+    ("prompt-type", "Enter the type of the commit (e.g., feat, fix): "),
+    ("prompt-scope", "Enter the scope of the commit (e.g., ui, api): "),
+    ("prompt-message", "Enter the message for the commit: "),
+    ("prompt-reuse", "Reuse a recent commit"),
+    ("reuse-needs-terminal", "--reuse needs a terminal to pick a commit"),
+    ("nothing-to-reuse", "Warning: no conventional commits in the last {limit} to reuse"),
+    ("confirm-guessed-type", "Use type '{type}' for this message?"),
+    ("confirm-existing-scope", "Scope '{scope}' is already used as '{canonical}'. Use '{canonical}'?"),
+    ("body-template", "finish adds this template to the body of {type} commits:"),
+    ("inferred-scope", "Inferred scope: {scope}"),
+    ("using-existing-scope", "Using existing scope: {scope}"),
+    ("remote-missing", "Remote '{remote}' does not exist"),
+    ("tracking", "Tracking {remote}/{branch}"),
+    ("branch-exists", "Branch {branch} already exists, pass --resume to check it out"),
+    ("branch-created", "Branch created and checked out: {branch}"),
+    ("branch-resumed", "Resumed existing branch: {branch}"),
+    ("branch-created-staying", "Branch created: {branch} (staying on {current})"),
+    ("branch-updated-staying", "Updated existing branch: {branch} (staying on {current})"),
+    ("dirty-tree", "The working tree has uncommitted changes:\n  {paths}\nCommit or stash them (`git stash`) before starting a new branch"),
+    ("checkout-conflict", "Checking out {branch} would overwrite local changes to:\n  {paths}\nCommit or stash them, or pass --force to discard them"),
+    ("switch-to-branch", "the switch to {branch}"),
+    ("would-track", "Would track {remote}/{branch}"),
+    ("would-create-branch", "Would create branch: {branch}"),
+    ("would-resume-branch", "Would resume existing branch: {branch}"),
+    ("would-write-metadata", "Would write metadata to: {path}"),
+    ("input-type", "commit type"),
+    ("input-message", "commit message"),
+    ("missing-input", "Missing {input}: pass {flag}, prompts are disabled with --yes or without a terminal"),
+    ("invalid-preserve-pattern", "Invalid preserve_pattern: {error}"),
+    ("invalid-scope-separator", "Invalid scope_separator {separator}, it can't be empty or contain '/' or spaces"),
+    ("unknown-type", "Type `{type}` must be one of: {types}"),
+    ("metadata-not-restored", "Warning: could not restore {path}: {error}"),
+    ("branch-not-deleted", "Warning: could not delete branch {branch}: {error}"),
+    // finish
+    ("nothing-to-commit", "Nothing to commit, stage some changes first or pass --allow-empty for an empty commit. To only update the last commit's message, pass --reword-only"),
+    ("protected-branch", "Refusing to commit on protected branch '{branch}'. Run `gwf nfb` to create a feature branch first, or pass --force"),
+    ("tag-exists", "Tag '{tag}' already exists, pass --force to move it"),
+    ("onto-exists", "Branch {branch} already exists, pick another name for --onto"),
+    ("would-create-commit", "Would create commit on {branch}:\n\n{message}"),
+    ("would-run-post-commit", "Would run post-commit command: {command}"),
+    ("would-create-annotated-tag", "Would create annotated tag: {tag}"),
+    ("would-create-lightweight-tag", "Would create lightweight tag: {tag}"),
+    ("created-commit", "Created commit: {commit}"),
+    ("committed-tree-from", "Committed the tree of {ref}, the index and working tree were left as they were"),
+    ("created-tag", "Created tag: {tag}"),
+    ("post-commit-rolled-back", "Post-commit command failed, commit {commit} was rolled back"),
+    ("post-commit-failed", "Post-commit command failed after creating commit {commit}"),
+    ("reworded-commit", "Reworded commit: {commit}"),
+    ("no-commits-yet", "There are no commits yet, this is the first one"),
+    ("would-create-onto", "Would create and check out branch: {branch}"),
+    ("tree-not-found", "Could not find a tree for '{ref}': {error}"),
+    ("no-merge-base-shallow", "No merge base with {commit} in the fetched history, deepen the clone with `git fetch --deepen` or `--unshallow`"),
+    ("cannot-amend-root", "Cannot amend the root commit"),
+    ("keep-message-unchanged", "Nothing new is staged, so amending with --keep-message would not change the commit"),
+    ("unexpected-domain", "Committer email '{email}' is not in the expected domain '{domain}'. Fix user.email or pass --force"),
+    ("unexpected-domain-forced", "Warning: committer email '{email}' is not in the expected domain '{domain}'"),
+    ("long-lines", "Warning: commit message lines longer than {width} characters: {lines}. Pass --wrap to reflow the body"),
+    ("confirm-amend-pushed", "Commit {commit} appears to be pushed to {upstream}. Amend it anyway?"),
+    ("aborted-amend-pushed", "Aborted amending a pushed commit, pass --yes to rewrite it"),
+    ("would-amend", "Would amend commit {commit}"),
+    ("would-squash", "Would squash {count} commit(s) since {commit}"),
+    ("squashed", "Squashed {count} commit(s) since {commit}"),
+    ("amended-commit", "Amended commit {commit}"),
+    ("ahead-behind", "{branch} is now {ahead} commit(s) ahead of {base} and {behind} behind"),
+    ("metadata-missing", "Warning: original message not found at {path}, deriving it from the branch name"),
+    ("invalid-branch-format", "Invalid branch name format. Expected: type/scope/message or type/message"),
+    ("invalid-header", "Invalid commit header `{header}`: {error}"),
+    ("invalid-ticket-pattern", "Invalid ticket_pattern: {error}"),
+    ("invalid-trailer", "Invalid trailer `{trailer}` in config, expected `Key: value`"),
+    ("missing-trailer-value", "Missing value for trailer `{key}: {value}`: set it in the trailers config, prompts are disabled with --yes or without a terminal"),
+    ("no-message-in-file", "{path} holds no message"),
+    ("file-header-not-conventional", "Warning: the header `{header}` from {path} is not a conventional commit: {error}"),
+    ("fixup-target-missing", "Could not find commit '{commit}' to fix up: {error}"),
+    ("nothing-staged", "Nothing is staged"),
+    ("nothing-staged-under", "Nothing is staged under {paths}"),
+    ("interactive-needs-terminal", "--interactive needs a terminal to pick files, pass paths after -- instead"),
+    ("select-staged-files", "Select the staged files to commit"),
+    ("no-files-selected", "No files selected"),
+    ("no-repo-root", "Could not get repository root"),
+    ("pre-commit-missing", "Warning: {config} exists but pre-commit is not installed, skipping hooks"),
+    ("pre-commit-not-run", "Could not run pre-commit: {error}"),
+    ("pre-commit-failed", "pre-commit failed with exit code {code}"),
+    ("pre-commit-hooks-failed", "pre-commit hooks failed: {hooks}"),
+    ("rolling-back", "Warning: rolling back the commit because the post-commit command failed"),
+    ("rolled-back", "Warning: HEAD is back at {commit} and the changes are still staged"),
+    ("rolled-back-unborn", "Warning: the branch has no commits again and the changes are still staged"),
+    ("post-commit-output", "Post-commit command output:"),
+    ("post-commit-errors", "Post-commit command errors:"),
+    ("post-commit-succeeded", "Post-commit command executed successfully"),
+    ("post-commit-exit-code", "Post-commit command failed with exit code: {code}"),
+    ("stashed", "Stashed unstaged changes"),
+    ("stash-restored", "Restored stashed changes"),
+    ("stash-not-dropped", "Warning: restored stashed changes but could not drop the stash: {error}"),
+    ("stash-not-restored", "Warning: could not restore stashed changes ({error}). They are kept in stash@{0}, run `git stash pop` to recover them"),
+    ("editor-instructions", "Edit the commit message. Lines starting with '#' are ignored,\nan empty message aborts the commit."),
+    ("editor-not-saved", "Aborted: the message was not saved"),
+    ("editor-empty", "Aborted: empty commit message"),
+    ("no-base-branch", "No base branch, pass --base or set base_branch in the config"),
+    ("base-branch-missing", "Could not resolve base branch '{branch}'"),
+    ("would-reword", "Would reword commit {commit}:\n\n{message}"),
+    ("confirm-rewrite-pushed", "Commit {commit} appears to be pushed to {upstream}. Rewrite it anyway?"),
+    ("aborted-reword-pushed", "Aborted rewording a pushed commit, pass --yes to rewrite it"),
+    ("would-change-author", "Would change the author of commit {commit} to {name} <{email}>"),
+    ("aborted-change-author-pushed", "Aborted changing the author of a pushed commit, pass --yes to rewrite it"),
+    ("changed-author", "Changed the author of commit {commit} to {name} <{email}>: {new}"),
+    ("invalid-author", "Invalid author `{author}`, expected `Name <email>`"),
+    ("signing-not-run", "Could not run {program} to sign the commit: {error}"),
+    ("signing-no-stdin", "Could not write to the signing program"),
+    ("signing-failed", "Signing the commit failed:\n{error}"),
+    ("no-commit-headers-end", "Could not find the end of the commit headers"),
+    ("no-current-branch", "Could not get current branch name"),
+    ("date-needs-utf8", "{field} is not valid UTF-8, so its date can't be changed. Drop --date, --author-date and --committer-date, or fix {field}"),
+    // shared by both
+    ("none", "none"),
+    ("interrupted", "Interrupted, waited for {what} to finish"),
+    ("invalid-date", "Could not parse date '{date}'. Use e.g. \"2024-01-31T14:00:00+01:00\", \"2024-01-31 14:00\" or \"@1706706000 +0100\""),
+    ("invalid-env-date", "Invalid {var}: {error}"),
+    ("not-utf8", "{field} is not valid UTF-8"),
+    ("invalid-utf8-replaced", "Warning: {source} is not valid UTF-8, invalid bytes were replaced"),
+    ("not-latin1", "The message contains '{char}', which ISO-8859-1 cannot represent"),
+    ("unsupported-encoding", "Unsupported message encoding `{encoding}`, use UTF-8 or ISO-8859-1"),
+    ("invalid-metadata-name", "Branch name {branch} does not map to a valid metadata file name"),
+    ("not-a-metadata-file", "Refusing to remove {path}: it is not a metadata file under {dir}"),
+    ("invalid-breaking-token", "Invalid breaking_change_token `{token}`, expected one of: {tokens}"),
+    ("unknown-identity", "Unknown identity '{name}'. Available identities: {available}"),
+    ("unknown-profile", "Unknown profile '{profile}'. Available profiles: {available}"),
+    ("invalid-config", "Invalid config: {error}"),
+    ("config-file-missing", "Config file {path} does not exist"),
+];
+
+const ES: &[(&str, &str)] = &[
+    // nfb
+    ("prompt-type", "Introduce el tipo del commit (p. ej., feat, fix): "),
+    ("prompt-scope", "Introduce el ámbito del commit (p. ej., ui, api): "),
+    ("prompt-message", "Introduce el mensaje del commit: "),
+    ("prompt-reuse", "Reutilizar un commit reciente"),
+    ("reuse-needs-terminal", "--reuse necesita una terminal para elegir un commit"),
+    ("nothing-to-reuse", "Aviso: no hay commits convencionales entre los últimos {limit} para reutilizar"),
+    ("confirm-guessed-type", "¿Usar el tipo '{type}' para este mensaje?"),
+    ("confirm-existing-scope", "El ámbito '{scope}' ya se usa como '{canonical}'. ¿Usar '{canonical}'?"),
+    ("body-template", "finish añade esta plantilla al cuerpo de los commits {type}:"),
+    ("inferred-scope", "Ámbito inferido: {scope}"),
+    ("using-existing-scope", "Usando el ámbito existente: {scope}"),
+    ("remote-missing", "El remoto '{remote}' no existe"),
+    ("tracking", "Siguiendo {remote}/{branch}"),
+    ("branch-exists", "La rama {branch} ya existe, usa --resume para cambiar a ella"),
+    ("branch-created", "Rama creada y activa: {branch}"),
+    ("branch-resumed", "Retomada la rama existente: {branch}"),
+    ("branch-created-staying", "Rama creada: {branch} (sigues en {current})"),
+    ("branch-updated-staying", "Rama existente actualizada: {branch} (sigues en {current})"),
+    ("dirty-tree", "El directorio de trabajo tiene cambios sin commit:\n  {paths}\nHaz commit o stash de ellos (`git stash`) antes de empezar una rama nueva"),
+    ("checkout-conflict", "Cambiar a {branch} sobrescribiría cambios locales en:\n  {paths}\nHaz commit o stash de ellos, o usa --force para descartarlos"),
+    ("switch-to-branch", "el cambio a {branch}"),
+    ("would-track", "Se seguiría {remote}/{branch}"),
+    ("would-create-branch", "Se crearía la rama: {branch}"),
+    ("would-resume-branch", "Se retomaría la rama existente: {branch}"),
+    ("would-write-metadata", "Se escribirían los metadatos en: {path}"),
+    ("input-type", "tipo del commit"),
+    ("input-message", "mensaje del commit"),
+    ("missing-input", "Falta el {input}: usa {flag}, no se pregunta nada con --yes o sin terminal"),
+    ("invalid-preserve-pattern", "preserve_pattern no válido: {error}"),
+    ("invalid-scope-separator", "scope_separator {separator} no válido, no puede estar vacío ni contener '/' o espacios"),
+    ("unknown-type", "El tipo `{type}` debe ser uno de: {types}"),
+    ("metadata-not-restored", "Aviso: no se pudo restaurar {path}: {error}"),
+    ("branch-not-deleted", "Aviso: no se pudo borrar la rama {branch}: {error}"),
+    // finish
+    ("nothing-to-commit", "No hay nada para el commit, añade cambios al índice primero o usa --allow-empty para un commit vacío. Para cambiar solo el mensaje del último commit, usa --reword-only"),
+    ("protected-branch", "No se hace commit en la rama protegida '{branch}'. Ejecuta `gwf nfb` para crear una rama primero, o usa --force"),
+    ("tag-exists", "La etiqueta '{tag}' ya existe, usa --force para moverla"),
+    ("onto-exists", "La rama {branch} ya existe, elige otro nombre para --onto"),
+    ("would-create-commit", "Se crearía el commit en {branch}:\n\n{message}"),
+    ("would-run-post-commit", "Se ejecutaría el comando post-commit: {command}"),
+    ("would-create-annotated-tag", "Se crearía la etiqueta anotada: {tag}"),
+    ("would-create-lightweight-tag", "Se crearía la etiqueta ligera: {tag}"),
+    ("created-commit", "Commit creado: {commit}"),
+    ("committed-tree-from", "Se usó el árbol de {ref}, el índice y el directorio de trabajo no se tocaron"),
+    ("created-tag", "Etiqueta creada: {tag}"),
+    ("post-commit-rolled-back", "Falló el comando post-commit, se deshizo el commit {commit}"),
+    ("post-commit-failed", "Falló el comando post-commit después de crear el commit {commit}"),
+    ("reworded-commit", "Mensaje del commit cambiado: {commit}"),
+    ("no-commits-yet", "Todavía no hay commits, este es el primero"),
+    ("would-create-onto", "Se crearía y activaría la rama: {branch}"),
+    ("tree-not-found", "No se encontró un árbol para '{ref}': {error}"),
+    ("no-merge-base-shallow", "No hay base común con {commit} en el historial descargado, amplía el clon con `git fetch --deepen` o `--unshallow`"),
+    ("cannot-amend-root", "No se puede modificar el commit raíz"),
+    ("keep-message-unchanged", "No hay nada nuevo en el índice, así que modificar con --keep-message no cambiaría el commit"),
+    ("unexpected-domain", "El email del committer '{email}' no está en el dominio esperado '{domain}'. Corrige user.email o usa --force"),
+    ("unexpected-domain-forced", "Aviso: el email del committer '{email}' no está en el dominio esperado '{domain}'"),
+    ("long-lines", "Aviso: líneas del mensaje de más de {width} caracteres: {lines}. Usa --wrap para reajustar el cuerpo"),
+    ("confirm-amend-pushed", "El commit {commit} parece estar subido a {upstream}. ¿Modificarlo de todos modos?"),
+    ("aborted-amend-pushed", "Se canceló la modificación de un commit subido, usa --yes para reescribirlo"),
+    ("would-amend", "Se modificaría el commit {commit}"),
+    ("would-squash", "Se combinarían {count} commit(s) desde {commit}"),
+    ("squashed", "Combinados {count} commit(s) desde {commit}"),
+    ("amended-commit", "Commit modificado: {commit}"),
+    ("ahead-behind", "{branch} está ahora {ahead} commit(s) por delante de {base} y {behind} por detrás"),
+    ("metadata-missing", "Aviso: no se encontró el mensaje original en {path}, se deriva del nombre de la rama"),
+    ("invalid-branch-format", "Formato de nombre de rama no válido. Se espera: tipo/ámbito/mensaje o tipo/mensaje"),
+    ("invalid-header", "Cabecera de commit no válida `{header}`: {error}"),
+    ("invalid-ticket-pattern", "ticket_pattern no válido: {error}"),
+    ("invalid-trailer", "Trailer `{trailer}` no válido en la configuración, se espera `Clave: valor`"),
+    ("missing-trailer-value", "Falta el valor del trailer `{key}: {value}`: ponlo en la configuración de trailers, no se pregunta nada con --yes o sin terminal"),
+    ("no-message-in-file", "{path} no contiene ningún mensaje"),
+    ("file-header-not-conventional", "Aviso: la cabecera `{header}` de {path} no es un commit convencional: {error}"),
+    ("fixup-target-missing", "No se encontró el commit '{commit}' para el fixup: {error}"),
+    ("nothing-staged", "No hay nada en el índice"),
+    ("nothing-staged-under", "No hay nada en el índice bajo {paths}"),
+    ("interactive-needs-terminal", "--interactive necesita una terminal para elegir archivos, pasa las rutas después de -- en su lugar"),
+    ("select-staged-files", "Elige los archivos del índice que van en el commit"),
+    ("no-files-selected", "No se eligió ningún archivo"),
+    ("no-repo-root", "No se pudo obtener la raíz del repositorio"),
+    ("pre-commit-missing", "Aviso: {config} existe pero pre-commit no está instalado, se omiten los hooks"),
+    ("pre-commit-not-run", "No se pudo ejecutar pre-commit: {error}"),
+    ("pre-commit-failed", "pre-commit falló con el código de salida {code}"),
+    ("pre-commit-hooks-failed", "Fallaron los hooks de pre-commit: {hooks}"),
+    ("rolling-back", "Aviso: se deshace el commit porque falló el comando post-commit"),
+    ("rolled-back", "Aviso: HEAD vuelve a estar en {commit} y los cambios siguen en el índice"),
+    ("rolled-back-unborn", "Aviso: la rama vuelve a no tener commits y los cambios siguen en el índice"),
+    ("post-commit-output", "Salida del comando post-commit:"),
+    ("post-commit-errors", "Errores del comando post-commit:"),
+    ("post-commit-succeeded", "El comando post-commit terminó correctamente"),
+    ("post-commit-exit-code", "El comando post-commit falló con el código de salida: {code}"),
+    ("stashed", "Cambios fuera del índice guardados en el stash"),
+    ("stash-restored", "Cambios del stash restaurados"),
+    ("stash-not-dropped", "Aviso: se restauraron los cambios del stash pero no se pudo borrar el stash: {error}"),
+    ("stash-not-restored", "Aviso: no se pudieron restaurar los cambios del stash ({error}). Se conservan en stash@{0}, ejecuta `git stash pop` para recuperarlos"),
+    ("editor-instructions", "Edita el mensaje del commit. Las líneas que empiezan por '#' se ignoran,\nun mensaje vacío cancela el commit."),
+    ("editor-not-saved", "Cancelado: no se guardó el mensaje"),
+    ("editor-empty", "Cancelado: mensaje de commit vacío"),
+    ("no-base-branch", "No hay rama base, usa --base o pon base_branch en la configuración"),
+    ("base-branch-missing", "No se pudo resolver la rama base '{branch}'"),
+    ("would-reword", "Se cambiaría el mensaje del commit {commit}:\n\n{message}"),
+    ("confirm-rewrite-pushed", "El commit {commit} parece estar subido a {upstream}. ¿Reescribirlo de todos modos?"),
+    ("aborted-reword-pushed", "Se canceló el cambio de mensaje de un commit subido, usa --yes para reescribirlo"),
+    ("would-change-author", "Se cambiaría el autor del commit {commit} a {name} <{email}>"),
+    ("aborted-change-author-pushed", "Se canceló el cambio de autor de un commit subido, usa --yes para reescribirlo"),
+    ("changed-author", "Autor del commit {commit} cambiado a {name} <{email}>: {new}"),
+    ("invalid-author", "Autor `{author}` no válido, se espera `Nombre <email>`"),
+    ("signing-not-run", "No se pudo ejecutar {program} para firmar el commit: {error}"),
+    ("signing-no-stdin", "No se pudo escribir en el programa de firma"),
+    ("signing-failed", "Falló la firma del commit:\n{error}"),
+    ("no-commit-headers-end", "No se encontró el final de las cabeceras del commit"),
+    ("no-current-branch", "No se pudo obtener el nombre de la rama actual"),
+    ("date-needs-utf8", "{field} no es UTF-8 válido, así que no se puede cambiar su fecha. Quita --date, --author-date y --committer-date, o corrige {field}"),
+    // shared by both
+    ("none", "ninguno"),
+    ("interrupted", "Interrumpido, se esperó a que terminara {what}"),
+    ("invalid-date", "No se pudo interpretar la fecha '{date}'. Usa p. ej. \"2024-01-31T14:00:00+01:00\", \"2024-01-31 14:00\" o \"@1706706000 +0100\""),
+    ("invalid-env-date", "{var} no válido: {error}"),
+    ("not-utf8", "{field} no es UTF-8 válido"),
+    ("invalid-utf8-replaced", "Aviso: {source} no es UTF-8 válido, se reemplazaron los bytes no válidos"),
+    ("not-latin1", "El mensaje contiene '{char}', que ISO-8859-1 no puede representar"),
+    ("unsupported-encoding", "Codificación del mensaje `{encoding}` no soportada, usa UTF-8 o ISO-8859-1"),
+    ("invalid-metadata-name", "El nombre de rama {branch} no corresponde a un nombre de archivo de metadatos válido"),
+    ("not-a-metadata-file", "No se borra {path}: no es un archivo de metadatos dentro de {dir}"),
+    ("invalid-breaking-token", "breaking_change_token `{token}` no válido, se espera uno de: {tokens}"),
+    ("unknown-identity", "Identidad '{name}' desconocida. Identidades disponibles: {available}"),
+    ("unknown-profile", "Perfil '{profile}' desconocido. Perfiles disponibles: {available}"),
+    ("invalid-config", "Configuración no válida: {error}"),
+    ("config-file-missing", "El archivo de configuración {path} no existe"),
+];
+
+// GWF_LANG wins over LANG, anything that isn't Spanish is English
+fn lang() -> Lang {
+    static LANG: OnceLock<Lang> = OnceLock::new();
+    *LANG.get_or_init(|| {
+        let value = env::var(GWF_LANG_ENV).or_else(|_| env::var("LANG")).unwrap_or_default();
+        if value.to_lowercase().starts_with("es") {
+            Lang::Es
+        } else {
+            Lang::En
+        }
+    })
+}
+
+// The string for `key` in the current language with its placeholders filled,
+// falling back to English when there is no translation
+pub fn t(key: &str, values: &[(&str, &str)]) -> String {
+    let lookup = |table: &[(&str, &'static str)]| table.iter().find(|(k, _)| *k == key).map(|(_, text)| *text);
+    let translated = match lang() {
+        Lang::Es => lookup(ES),
+        Lang::En => None,
+    };
+    let template = translated.or_else(|| lookup(EN)).unwrap_or(key);
+    fill_placeholders(template, values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn placeholders(text: &str) -> BTreeSet<&str> {
+        text.split('{').skip(1).filter_map(|rest| rest.split_once('}')).map(|(name, _)| name).filter(|name| !name.contains(' ')).collect()
+    }
+
+    #[test]
+    fn every_string_is_translated() {
+        let en: BTreeSet<&str> = EN.iter().map(|(key, _)| *key).collect();
+        let es: BTreeSet<&str> = ES.iter().map(|(key, _)| *key).collect();
+        assert_eq!(en.difference(&es).collect::<Vec<_>>(), Vec::<&&str>::new(), "missing in ES");
+        assert_eq!(es.difference(&en).collect::<Vec<_>>(), Vec::<&&str>::new(), "unknown in ES");
+        assert_eq!(en.len(), EN.len(), "duplicate keys in EN");
+        assert_eq!(es.len(), ES.len(), "duplicate keys in ES");
+    }
+
+    // Every key passed to `t` in the sources exists
+    #[test]
+    fn every_used_key_is_defined() {
+        let mut dirs = vec![std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src")];
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(&dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                let source = std::fs::read_to_string(&path).unwrap();
+                for (start, call) in source.match_indices("t(\"") {
+                    // Skip calls like `insert("...")`
+                    if source[..start].ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+                        continue;
+                    }
+                    let key = source[start + call.len()..].split('"').next().unwrap_or_default();
+                    if !key.is_empty() && key.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-') {
+                        assert!(EN.iter().any(|(k, _)| *k == key), "{} uses undefined key {}", path.display(), key);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn translations_keep_the_placeholders() {
+        for (key, text) in ES {
+            let english = EN.iter().find(|(k, _)| k == key).map(|(_, text)| *text).unwrap_or_default();
+            assert_eq!(placeholders(text), placeholders(english), "{}", key);
+        }
+    }
+}
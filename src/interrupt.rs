@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::i18n::t;
+
+// Set while a Ctrl-C has to wait for the running step to finish
+static DEFERRING: AtomicBool = AtomicBool::new(false);
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+// Whether stderr is a terminal, looked up before any signal arrives
+static STDERR_TTY: AtomicBool = AtomicBool::new(false);
+
+// Exit code of a process stopped by SIGINT, like the shell reports it
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+// dialoguer hides the cursor while a selection prompt is open
+const SHOW_CURSOR: &[u8] = b"\x1b[?25h\n";
+
+// Handle Ctrl-C: leave the terminal usable instead of with a hidden cursor,
+// and let steps run through `deferred` finish first
+#[cfg(unix)]
+pub fn install() {
+    use std::io::IsTerminal;
+
+    STDERR_TTY.store(std::io::stderr().is_terminal(), Ordering::SeqCst);
+    let handler = on_sigint as extern "C" fn(libc::c_int);
+    // SAFETY: the handler only touches atomics and calls write and _exit,
+    // which are async-signal-safe
+    unsafe {
+        libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+    }
+}
+
+// Windows keeps the default handling
+#[cfg(not(unix))]
+pub fn install() {}
+
+#[cfg(unix)]
+extern "C" fn on_sigint(_: libc::c_int) {
+    if DEFERRING.load(Ordering::SeqCst) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+        return;
+    }
+    // SAFETY: see install
+    unsafe {
+        if STDERR_TTY.load(Ordering::SeqCst) {
+            libc::write(libc::STDERR_FILENO, SHOW_CURSOR.as_ptr().cast(), SHOW_CURSOR.len());
+        }
+        libc::_exit(INTERRUPTED_EXIT_CODE);
+    }
+}
+
+// Run a step that must not be cut short, such as creating a branch together
+// with its metadata. A Ctrl-C during it exits once it is done
+pub fn deferred<T>(what: &str, step: impl FnOnce() -> T) -> T {
+    DEFERRING.store(true, Ordering::SeqCst);
+    let result = step();
+    DEFERRING.store(false, Ordering::SeqCst);
+    if INTERRUPTED.load(Ordering::SeqCst) {
+        eprintln!("{}", t("interrupted", &[("what", what)]));
+        std::process::exit(INTERRUPTED_EXIT_CODE);
+    }
+    result
+}
@@ -1,43 +1,352 @@
-use clap::{Arg, Command};
-use serde::{Deserialize, Serialize};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::path::PathBuf;
+mod color;
 mod commands;
+mod config;
+mod conventional;
+mod date;
+mod diff;
+mod error;
+mod history;
+mod i18n;
+mod interrupt;
+mod message;
+mod metadata;
+mod prompt;
+mod scope;
+mod signature;
 
-use commands::nfb::{new_branch, prompt_user};
-use commands::finish::finish;
+use config::ConfigArgs;
 
+use commands::export::export;
+use commands::hook::install_hook;
+use commands::nfb::{nfb, NfbOptions};
+use commands::prune::prune;
+use commands::branches::branches;
+use commands::bump::bump;
+use commands::changelog::{changelog, ChangelogOptions};
+use commands::config::show_config;
+use commands::doctor::doctor;
+use commands::finish::{finish, FinishOptions};
+use commands::lint::lint;
+use commands::note::note;
+use commands::revert::revert;
+use commands::status::status;
+use commands::switch::switch;
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Config {
-    post_commit_command: String,
+// Like `git commit -m a -m b`: the first value is the subject, the rest are
+// body paragraphs
+fn joined_paragraphs(matches: &ArgMatches, id: &str) -> Option<String> {
+    let paragraphs: Vec<&str> = matches.get_many::<String>(id)?.map(String::as_str).collect();
+    Some(paragraphs.join("\n\n"))
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let matches = Command::new("git-workflow")
         .subcommand_required(true)
         .arg_required_else_help(true)
+        .arg(Arg::new("config").long("config").value_name("PATH").global(true).value_parser(clap::value_parser!(PathBuf)).help("Load exactly this config file instead of searching for one"))
+        .arg(Arg::new("profile").long("profile").value_name("NAME").global(true).help("Apply a [profiles.NAME] table from the home config (or GWF_PROFILE)"))
+        .arg(Arg::new("no-color").long("no-color").global(true).action(ArgAction::SetTrue).help("Don't color the output (also NO_COLOR=1 or CLICOLOR=0)"))
+        .arg(Arg::new("yes").short('y').long("yes").visible_alias("assume-yes").global(true).action(ArgAction::SetTrue).help("Never prompt: answer yes to confirmations and fail on missing required values"))
         .subcommand(
             Command::new("nfb")
                 .about("Create a new feature branch with a conventional commit message")
                 .arg(Arg::new("type").short('t').long("type").value_name("TYPE").help("Type of the commit (e.g., feat, fix)"))
                 .arg(Arg::new("scope").short('s').long("scope").value_name("SCOPE").help("Scope of the commit (e.g., ui, api)"))
-                .arg(Arg::new("message").short('m').long("message").value_name("MESSAGE").help("Message for the commit")),
+                .arg(Arg::new("message").short('m').long("message").value_name("MESSAGE").action(ArgAction::Append).help("Message for the commit, repeat for body paragraphs"))
+                .arg(Arg::new("resume").short('r').long("resume").action(ArgAction::SetTrue).help("Check out the branch if it already exists and rewrite its metadata"))
+                .arg(Arg::new("no-metadata").long("no-metadata").action(ArgAction::SetTrue).help("Don't store the message outside git, the branch name holds everything"))
+                .arg(Arg::new("switch-back-after").long("switch-back-after").action(ArgAction::SetTrue).help("Create the branch without checking it out"))
+                .arg(Arg::new("track").long("track").value_name("REMOTE").help("Set the branch's upstream to REMOTE/<branch>, so `git push` works right away"))
+                .arg(Arg::new("type-from-message").long("type-from-message").action(ArgAction::SetTrue).help("Guess the type from keywords in the message when --type isn't given"))
+                .arg(Arg::new("force").short('f').long("force").action(ArgAction::SetTrue).help("Discard local changes that would block checking out the branch"))
+                .arg(Arg::new("require-clean").long("require-clean").action(ArgAction::SetTrue).help("Refuse to start the branch while there are staged or unstaged changes"))
+                .arg(Arg::new("scope-from-cwd").long("scope-from-cwd").conflicts_with("scope").action(ArgAction::SetTrue).help("Use the current directory's name as the scope, e.g. `api` in packages/api"))
+                .arg(Arg::new("reuse").long("reuse").action(ArgAction::SetTrue).help("Start from a recent commit's type, scope and subject, editing the subject"))
+                .arg(Arg::new("dry-run").short('n').long("dry-run").action(ArgAction::SetTrue).help("Validate the input and show the branch and metadata file without creating anything"))
+                .after_help("Examples:\n  gwf nfb -t feat -s api -m \"add endpoint\"\n  gwf nfb -t fix -s \"\" -m \"handle empty input\"\n  gwf nfb -t fix -s parser -m \"handle empty input\" -m \"An empty file used to panic.\""),
         )
         .subcommand(
             Command::new("finish")
-                .about("Commit changes and run a post-commit command"),
+                .about("Commit changes and run a post-commit command")
+                .arg(Arg::new("type").short('t').long("type").value_name("TYPE").help("Override the type derived from the branch name"))
+                .arg(Arg::new("scope").short('s').long("scope").value_name("SCOPE").help("Override the scope derived from the branch name"))
+                .arg(Arg::new("message").short('m').long("message").value_name("MESSAGE").action(ArgAction::Append).help("Override the stored commit message, repeat for body paragraphs"))
+                .arg(Arg::new("onto").long("onto").value_name("BRANCH").conflicts_with_all(["reword", "amend-author"]).help("Create and check out BRANCH from HEAD, then commit there"))
+                .arg(Arg::new("force").short('f').long("force").action(ArgAction::SetTrue).help("Commit even if the branch is protected or the author domain doesn't match, and overwrite an existing --tag"))
+                .arg(Arg::new("show-diff").short('d').long("show-diff").action(ArgAction::SetTrue).help("Print the staged diff before committing"))
+                .arg(Arg::new("reword").long("reword").visible_alias("reword-only").action(ArgAction::SetTrue).help("Only replace the last commit's message, keeping its tree"))
+                .arg(Arg::new("tag").long("tag").value_name("NAME").help("Tag the new commit"))
+                .arg(Arg::new("annotated").long("annotated").action(ArgAction::SetTrue).requires("tag").help("Create an annotated tag instead of a lightweight one"))
+                .arg(Arg::new("tag-message").long("tag-message").value_name("MESSAGE").requires("annotated").help("Message for the annotated tag, defaults to the commit message"))
+                .arg(Arg::new("squash").long("squash").action(ArgAction::SetTrue).help("Squash the branch's own commits and the staged changes into one commit"))
+                .arg(Arg::new("base").long("base").value_name("BRANCH").help("Branch the feature branch is based on, defaults to base_branch from the config. When given, also reports how far ahead of it the branch is"))
+                .arg(Arg::new("stash").long("stash").action(ArgAction::SetTrue).help("Stash unstaged changes while committing and restore them afterwards"))
+                .arg(Arg::new("edit").short('e').long("edit").action(ArgAction::SetTrue).help("Edit the commit message in your editor before committing"))
+                .arg(Arg::new("quiet").short('q').long("quiet").action(ArgAction::SetTrue).help("Only show post-commit command output when it fails"))
+                .arg(Arg::new("ignore-post-commit-failure").long("ignore-post-commit-failure").action(ArgAction::SetTrue).help("Exit successfully even if the post-commit command fails"))
+                .arg(Arg::new("raw-output").long("raw-output").action(ArgAction::SetTrue).help("Print the post-commit command output untouched"))
+                .arg(Arg::new("no-metadata").long("no-metadata").action(ArgAction::SetTrue).help("Ignore stored metadata and derive the message from the branch name"))
+                .arg(Arg::new("interactive").short('i').long("interactive").visible_alias("patch").action(ArgAction::SetTrue).help("Choose which staged files go into the commit"))
+                .arg(Arg::new("fixup").long("fixup").value_name("COMMIT").conflicts_with_all(["type", "scope", "message", "reword", "squash"]).help("Create a `fixup! <subject>` commit for autosquashing into COMMIT"))
+                .arg(Arg::new("explain").long("explain").action(ArgAction::SetTrue).help("Print to stderr how the message was derived: branch parts, metadata, type, scope and the final message"))
+                .arg(Arg::new("print-message").long("print-message").conflicts_with_all(["dry-run", "reword", "amend-author", "tag", "squash", "tree-from", "stash"]).action(ArgAction::SetTrue).help("Print the commit message and exit without committing, unlike --dry-run nothing else is checked"))
+                .arg(Arg::new("allow-empty").long("allow-empty").conflicts_with("reword").action(ArgAction::SetTrue).help("Commit even if nothing changed, e.g. to trigger CI"))
+                .arg(Arg::new("amend").long("amend").conflicts_with_all(["reword", "squash", "fixup"]).action(ArgAction::SetTrue).help("Replace the last commit with one including the staged changes"))
+                .arg(Arg::new("keep-message").long("keep-message").visible_alias("no-edit").requires("amend").conflicts_with_all(["edit", "type", "scope", "message", "wrap", "message-prefix", "message-suffix", "breaking", "closes", "co-author", "signoff"]).action(ArgAction::SetTrue).help("With --amend, reuse the last commit's message as is"))
+                .arg(Arg::new("from-editmsg").long("from-editmsg").conflicts_with_all(["fixup", "keep-message", "type", "scope", "message", "wrap", "message-prefix", "message-suffix", "breaking", "closes", "co-author", "signoff"]).action(ArgAction::SetTrue).help("Use the message in .git/COMMIT_EDITMSG as is, minus comments"))
+                .arg(Arg::new("amend-author").long("amend-author").value_name("NAME <EMAIL>").conflicts_with_all(["type", "scope", "message", "reword", "amend", "squash", "fixup", "from-editmsg", "tree-from", "edit"]).help("Only change the author of the last commit, keeping its message and tree"))
+                .arg(Arg::new("reset-committer").long("reset-committer").requires("amend-author").action(ArgAction::SetTrue).help("With --amend-author, also make you the committer"))
+                .arg(Arg::new("message-encoding").long("message-encoding").value_name("ENCODING").conflicts_with_all(["reword", "amend-author"]).help("Write the message in ENCODING (UTF-8 or ISO-8859-1) and record it in the commit's encoding header"))
+                .arg(Arg::new("reflog-message").long("reflog-message").value_name("TEXT").help("Use TEXT as the reflog entry of the commit instead of \"gwf finish: <subject>\""))
+                .arg(Arg::new("tree-from").long("tree-from").value_name("REF").conflicts_with_all(["interactive", "paths", "reword"]).help("Commit the tree of REF (a commit, branch or tag) instead of the index, on top of HEAD"))
+                .arg(Arg::new("paths").value_name("PATH").num_args(0..).last(true).help("Only commit the staged changes under these paths"))
+                .arg(Arg::new("wrap").long("wrap").action(ArgAction::SetTrue).help("Reflow the body to max_body_line_length"))
+                .arg(Arg::new("message-prefix").long("message-prefix").value_name("TEXT").help("Put this before the subject, after the type and scope"))
+                .arg(Arg::new("message-suffix").long("message-suffix").value_name("TEXT").help("Put this after the subject, e.g. \"[skip ci]\""))
+                .arg(Arg::new("identity").long("identity").value_name("NAME").help("Commit as an identity from the [identities] config table"))
+                .arg(Arg::new("signoff").long("signoff").action(ArgAction::SetTrue).help("Add a Signed-off-by trailer for the committer"))
+                .arg(Arg::new("co-author").long("co-author").value_name("NAME <EMAIL>").action(ArgAction::Append).help("Add a Co-authored-by trailer, can be repeated"))
+                .arg(Arg::new("closes").long("closes").value_name("ISSUE").action(ArgAction::Append).help("Add a Closes trailer, can be repeated"))
+                .arg(Arg::new("breaking").long("breaking").value_name("DESCRIPTION").help("Add a BREAKING CHANGE trailer"))
+                .arg(Arg::new("date").long("date").value_name("DATE").help("Author and committer date, e.g. 2024-01-31T14:00:00+01:00"))
+                .arg(Arg::new("author-date").long("author-date").value_name("DATE").help("Author date, overriding --date"))
+                .arg(Arg::new("committer-date").long("committer-date").value_name("DATE").help("Committer date, overriding --date"))
+                .arg(Arg::new("dry-run").short('n').long("dry-run").action(ArgAction::SetTrue).help("Show the diff and commit message without committing"))
+                .after_help("Examples:\n  gwf finish\n  gwf finish -t fix -m \"handle empty input\"\n  gwf finish -f -t chore -m \"bump version\"\n  gwf finish --onto feat/api/add-endpoint -m \"add endpoint\"\n  gwf finish --tag v1.2.0 --annotated\n  gwf finish --squash --base main\n  gwf finish -- src/api docs\n  gwf finish --fixup HEAD~2\n  gwf finish --date \"2024-01-31T14:00:00+01:00\"\n  gwf finish --signoff --co-author \"Ana <ana@example.com>\" --closes \"#42\""),
+        )
+        .subcommand(
+            Command::new("revert")
+                .about("Revert a commit with a conventional revert message")
+                .arg(Arg::new("commit").required(true).value_name("COMMIT").help("Commit to revert"))
+                .after_help("Examples:\n  gwf revert HEAD~2\n  gwf revert 1a2b3c4"),
+        )
+        .subcommand(
+            Command::new("lint")
+                .about("Check a commit message against the conventional commit format")
+                .arg(Arg::new("file").value_name("FILE").value_parser(clap::value_parser!(PathBuf)).help("File containing the message, reads stdin when omitted"))
+                .arg(Arg::new("format").long("format").value_name("FORMAT").value_parser(["human", "json"]).default_value("human").help("Output format"))
+                .after_help("Examples:\n  gwf lint .git/COMMIT_EDITMSG\n  git log -1 --format=%B | gwf lint --format json"),
+        )
+        .subcommand(
+            Command::new("bump")
+                .about("Suggest the next version from the commits since the last tag")
+                .arg(Arg::new("apply").long("apply").action(ArgAction::SetTrue).help("Tag HEAD with the suggested version"))
+                .after_help("Examples:\n  gwf bump\n  gwf bump --apply"),
+        )
+        .subcommand(
+            Command::new("changelog")
+                .about("Print the conventional commits in a range, grouped by type")
+                .arg(Arg::new("from").long("from").value_name("REF").help("Start after this commit, e.g. the last release tag"))
+                .arg(Arg::new("to").long("to").value_name("REF").help("End at this commit (defaults to HEAD)"))
+                .arg(Arg::new("since").long("since").value_name("DATE").help("Only commits made on or after this date"))
+                .arg(Arg::new("until").long("until").value_name("DATE").help("Only commits made on or before this date"))
+                .arg(Arg::new("type").short('t').long("type").value_name("TYPE").action(ArgAction::Append).help("Only include this type, can be repeated"))
+                .after_help("Examples:\n  gwf changelog --from v1.2.0\n  gwf changelog --since 2024-01-01 --until 2024-03-01\n  gwf changelog --from v1.2.0 -t feat -t fix"),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Print the conventional commits in a range as JSON for other tools")
+                .arg(Arg::new("from").long("from").value_name("REF").help("Start after this commit, e.g. the last release tag"))
+                .arg(Arg::new("to").long("to").value_name("REF").help("End at this commit (defaults to HEAD)"))
+                .arg(Arg::new("limit").long("limit").value_name("N").value_parser(clap::value_parser!(usize)).help("Export at most N commits"))
+                .arg(Arg::new("format").long("format").value_name("FORMAT").value_parser(["json"]).default_value("json").help("Output format"))
+                .after_help("Examples:\n  gwf export --from v1.2.0\n  gwf export --limit 100 > commits.json"),
+        )
+        .subcommand(
+            Command::new("switch")
+                .about("Pick a feature branch and check it out")
+                .arg(Arg::new("remote").short('r').long("remote").action(ArgAction::SetTrue).help("Include remote-tracking branches, creating a local tracking branch on checkout"))
+                .after_help("Examples:\n  gwf switch\n  gwf switch --remote"),
+        )
+        .subcommand(
+            Command::new("branches")
+                .about("List feature branches with their type, scope and stored subject")
+                .arg(Arg::new("sort").long("sort").value_name("KEY").value_parser(["name", "date"]).default_value("name").help("Sort by branch name or by last commit, newest first"))
+                .arg(Arg::new("json").long("json").action(ArgAction::SetTrue).help("Print the branches as JSON"))
+                .after_help("Examples:\n  gwf branches\n  gwf branches --sort date\n  gwf branches --json"),
+        )
+        .subcommand(
+            Command::new("status")
+                .about("Show the current feature branch, its upstream and the working tree state")
+                .arg(Arg::new("porcelain").long("porcelain").action(ArgAction::SetTrue).help("Print one tab-separated line for scripts: branch, type, scope, clean|dirty, ahead, behind"))
+                .arg(Arg::new("null").short('z').requires("porcelain").action(ArgAction::SetTrue).help("With --porcelain, separate and terminate the fields with NUL"))
+                .after_help("The --porcelain format is stable: fields are only ever added at the end, and missing values are \"-\"."),
+        )
+        .subcommand(
+            Command::new("note")
+                .about("Add a note to the current branch, finish puts the notes in the commit body")
+                .arg(Arg::new("text").value_name("TEXT").required_unless_present("clear").help("Note to add"))
+                .arg(Arg::new("clear").long("clear").action(ArgAction::SetTrue).conflicts_with("text").help("Remove all notes of the current branch"))
+                .after_help("Examples:\n  gwf note \"the cache is keyed by tenant now\"\n  gwf note --clear"),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Inspect gwf's configuration")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("show")
+                        .about("Print the effective configuration after merging files and profiles")
+                        .arg(Arg::new("verbose").short('v').long("verbose").action(ArgAction::SetTrue).help("Note where each setting came from")),
+                )
+                .after_help("Examples:\n  gwf config show\n  gwf --profile work config show --verbose"),
+        )
+        .subcommand(
+            Command::new("hook")
+                .about("Manage the git hooks gwf provides")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("install")
+                        .about("Install a commit-msg hook that runs `gwf lint`, honoring core.hooksPath")
+                        .arg(Arg::new("force").short('f').long("force").action(ArgAction::SetTrue).help("Replace an existing hook gwf didn't install")),
+                ),
+        )
+        .subcommand(
+            Command::new("prune")
+                .about("Remove metadata for deleted branches and metadata older than metadata_max_age_days")
+                .arg(Arg::new("dry-run").short('n').long("dry-run").action(ArgAction::SetTrue).help("List what would be removed without removing it")),
+        )
+        .subcommand(
+            Command::new("doctor")
+                .about("Check the environment for common setup problems"),
         )
         .get_matches();
 
+    let config_args = ConfigArgs {
+        path: matches.get_one::<PathBuf>("config").cloned(),
+        profile: matches.get_one::<String>("profile").cloned(),
+    };
+    let assume_yes = matches.get_flag("yes");
+    color::init(matches.get_flag("no-color"));
+    interrupt::install();
+
     match matches.subcommand() {
         Some(("nfb", sub_matches)) => {
-            let type_ = sub_matches.get_one::<String>("type").cloned().unwrap_or_else(|| prompt_user("Enter the type of the commit (e.g., feat, fix): "));
-            let scope = sub_matches.get_one::<String>("scope").cloned().unwrap_or_else(|| prompt_user("Enter the scope of the commit (e.g., ui, api): "));
-            let message = sub_matches.get_one::<String>("message").cloned().unwrap_or_else(|| prompt_user("Enter the message for the commit: "));
-
-            new_branch(&type_, &scope, &message)?;
+            let options = NfbOptions {
+                type_: sub_matches.get_one::<String>("type").cloned(),
+                scope: sub_matches.get_one::<String>("scope").cloned(),
+                message: joined_paragraphs(sub_matches, "message"),
+                config_args: config_args.clone(),
+                resume: sub_matches.get_flag("resume"),
+                no_metadata: sub_matches.get_flag("no-metadata"),
+                switch_back_after: sub_matches.get_flag("switch-back-after"),
+                track: sub_matches.get_one::<String>("track").cloned(),
+                assume_yes,
+                type_from_message: sub_matches.get_flag("type-from-message"),
+                force: sub_matches.get_flag("force"),
+                require_clean: sub_matches.get_flag("require-clean"),
+                dry_run: sub_matches.get_flag("dry-run"),
+                reuse: sub_matches.get_flag("reuse"),
+                scope_from_cwd: sub_matches.get_flag("scope-from-cwd"),
+            };
+            nfb(&options)?;
+        }
+        Some(("finish", sub_matches)) => {
+            let options = FinishOptions {
+                force: sub_matches.get_flag("force"),
+                type_: sub_matches.get_one::<String>("type").cloned(),
+                scope: sub_matches.get_one::<String>("scope").cloned(),
+                message: joined_paragraphs(sub_matches, "message"),
+                onto: sub_matches.get_one::<String>("onto").cloned(),
+                config_args: config_args.clone(),
+                show_diff: sub_matches.get_flag("show-diff"),
+                dry_run: sub_matches.get_flag("dry-run"),
+                reword: sub_matches.get_flag("reword"),
+                tag: sub_matches.get_one::<String>("tag").cloned(),
+                annotated: sub_matches.get_flag("annotated"),
+                tag_message: sub_matches.get_one::<String>("tag-message").cloned(),
+                squash: sub_matches.get_flag("squash"),
+                base: sub_matches.get_one::<String>("base").cloned(),
+                stash: sub_matches.get_flag("stash"),
+                edit: sub_matches.get_flag("edit"),
+                quiet: sub_matches.get_flag("quiet"),
+                ignore_post_commit_failure: sub_matches.get_flag("ignore-post-commit-failure"),
+                raw_output: sub_matches.get_flag("raw-output"),
+                date: sub_matches.get_one::<String>("date").cloned(),
+                author_date: sub_matches.get_one::<String>("author-date").cloned(),
+                committer_date: sub_matches.get_one::<String>("committer-date").cloned(),
+                no_metadata: sub_matches.get_flag("no-metadata"),
+                signoff: sub_matches.get_flag("signoff"),
+                co_authors: sub_matches.get_many::<String>("co-author").unwrap_or_default().cloned().collect(),
+                closes: sub_matches.get_many::<String>("closes").unwrap_or_default().cloned().collect(),
+                breaking: sub_matches.get_one::<String>("breaking").cloned(),
+                interactive: sub_matches.get_flag("interactive"),
+                identity: sub_matches.get_one::<String>("identity").cloned(),
+                message_prefix: sub_matches.get_one::<String>("message-prefix").cloned(),
+                message_suffix: sub_matches.get_one::<String>("message-suffix").cloned(),
+                paths: sub_matches.get_many::<String>("paths").unwrap_or_default().cloned().collect(),
+                fixup: sub_matches.get_one::<String>("fixup").cloned(),
+                tree_from: sub_matches.get_one::<String>("tree-from").cloned(),
+                amend: sub_matches.get_flag("amend"),
+                keep_message: sub_matches.get_flag("keep-message"),
+                from_editmsg: sub_matches.get_flag("from-editmsg"),
+                amend_author: sub_matches.get_one::<String>("amend-author").cloned(),
+                reset_committer: sub_matches.get_flag("reset-committer"),
+                reflog_message: sub_matches.get_one::<String>("reflog-message").cloned(),
+                message_encoding: sub_matches.get_one::<String>("message-encoding").cloned(),
+                allow_empty: sub_matches.get_flag("allow-empty"),
+                print_message: sub_matches.get_flag("print-message"),
+                explain: sub_matches.get_flag("explain"),
+                assume_yes,
+                wrap: sub_matches.get_flag("wrap"),
+            };
+            finish(&options)?;
+        }
+        Some(("revert", sub_matches)) => {
+            revert(sub_matches.get_one::<String>("commit").unwrap())?;
+        }
+        Some(("lint", sub_matches)) => {
+            let json = sub_matches.get_one::<String>("format").is_some_and(|f| f == "json");
+            lint(sub_matches.get_one::<PathBuf>("file").map(PathBuf::as_path), json, &config_args)?;
+        }
+        Some(("bump", sub_matches)) => {
+            bump(sub_matches.get_flag("apply"))?;
+        }
+        Some(("changelog", sub_matches)) => {
+            let options = ChangelogOptions {
+                from: sub_matches.get_one::<String>("from").cloned(),
+                to: sub_matches.get_one::<String>("to").cloned(),
+                since: sub_matches.get_one::<String>("since").cloned(),
+                until: sub_matches.get_one::<String>("until").cloned(),
+                types: sub_matches.get_many::<String>("type").unwrap_or_default().cloned().collect(),
+                config_args: config_args.clone(),
+            };
+            changelog(&options)?;
+        }
+        Some(("export", sub_matches)) => {
+            export(
+                sub_matches.get_one::<String>("from").map(String::as_str),
+                sub_matches.get_one::<String>("to").map(String::as_str),
+                sub_matches.get_one::<usize>("limit").copied(),
+            )?;
+        }
+        Some(("switch", sub_matches)) => {
+            switch(sub_matches.get_flag("remote"), assume_yes)?;
+        }
+        Some(("branches", sub_matches)) => {
+            let sort_by_date = sub_matches.get_one::<String>("sort").is_some_and(|s| s == "date");
+            branches(sort_by_date, sub_matches.get_flag("json"), &config_args)?;
+        }
+        Some(("status", sub_matches)) => {
+            status(sub_matches.get_flag("porcelain"), sub_matches.get_flag("null"), &config_args)?;
+        }
+        Some(("note", sub_matches)) => {
+            note(sub_matches.get_one::<String>("text").map(String::as_str), sub_matches.get_flag("clear"), &config_args)?;
+        }
+        Some(("config", sub_matches)) => match sub_matches.subcommand() {
+            Some(("show", show_matches)) => show_config(&config_args, show_matches.get_flag("verbose"))?,
+            _ => unreachable!(),
+        },
+        Some(("hook", sub_matches)) => match sub_matches.subcommand() {
+            Some(("install", install_matches)) => install_hook(install_matches.get_flag("force"))?,
+            _ => unreachable!(),
+        },
+        Some(("prune", sub_matches)) => {
+            prune(sub_matches.get_flag("dry-run"), &config_args)?;
         }
-        Some(("finish", _)) => {
-            finish()?;
+        Some(("doctor", _)) => {
+            doctor(&config_args)?;
         }
         _ => unreachable!(),
     }
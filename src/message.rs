@@ -0,0 +1,423 @@
+use crate::i18n::t;
+
+// Text from a file that should be UTF-8, replacing invalid bytes with a
+// warning naming `source` rather than failing outright
+pub fn lossy_utf8(bytes: Vec<u8>, source: &str) -> String {
+    String::from_utf8(bytes).unwrap_or_else(|e| {
+        eprintln!("{}", t("invalid-utf8-replaced", &[("source", source)]));
+        String::from_utf8_lossy(e.as_bytes()).into_owned()
+    })
+}
+
+// A commit message converted for a non-UTF-8 `encoding` header
+pub struct EncodedMessage {
+    // The name git records in the header
+    pub encoding: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+// Convert a message to the named encoding. None for UTF-8, which is git's
+// default and needs no header. Only ISO-8859-1 can be written besides it
+pub fn encode_message(message: &str, encoding: &str) -> Result<Option<EncodedMessage>, Box<dyn std::error::Error>> {
+    let name: String = encoding.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase();
+    match name.as_str() {
+        "utf8" => Ok(None),
+        "iso88591" | "latin1" | "l1" => {
+            let bytes = message
+                .chars()
+                .map(|c| u8::try_from(c).map_err(|_| t("not-latin1", &[("char", &c.to_string())])))
+                .collect::<Result<Vec<u8>, String>>()?;
+            Ok(Some(EncodedMessage { encoding: "ISO-8859-1", bytes }))
+        }
+        _ => Err(t("unsupported-encoding", &[("encoding", encoding)]).into()),
+    }
+}
+
+// Split an overly long subject at the first sentence boundary, or at the last
+// word boundary before `width` when the first sentence is itself too long,
+// moving the overflow into the body
+pub fn split_subject(message: &str, width: usize) -> (String, Option<String>) {
+    let message = message.trim();
+    let (subject, body) = match message.split_once('\n') {
+        Some((subject, body)) => (subject.trim(), Some(body.trim())),
+        None => (message, None),
+    };
+
+    if subject.chars().count() <= width {
+        return (subject.to_string(), body.map(str::to_string));
+    }
+
+    let split_at = sentence_boundary(subject)
+        .filter(|&index| subject[..index].chars().count() <= width)
+        .or_else(|| word_boundary(subject, width));
+    let (subject, overflow) = match split_at {
+        Some(index) => (subject[..index].trim_end(), subject[index..].trim_start()),
+        None => (subject, ""),
+    };
+
+    let body = [overflow, body.unwrap_or_default()]
+        .iter()
+        .filter(|part| !part.is_empty())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let subject = subject.trim_end_matches('.').to_string();
+
+    (subject, if body.is_empty() { None } else { Some(body) })
+}
+
+// Byte index just after the first ". ", "! " or "? "
+fn sentence_boundary(text: &str) -> Option<usize> {
+    text.char_indices()
+        .zip(text.chars().skip(1))
+        .find(|((_, c), next)| matches!(c, '.' | '!' | '?') && next.is_whitespace())
+        .map(|((i, c), _)| i + c.len_utf8())
+}
+
+// Byte index of the last whitespace within the first `width` characters
+fn word_boundary(text: &str, width: usize) -> Option<usize> {
+    text.char_indices()
+        .take(width + 1)
+        .filter(|(_, c)| c.is_whitespace())
+        .map(|(i, _)| i)
+        .last()
+}
+
+// Replace `{name}` placeholders with their values
+pub fn fill_placeholders(template: &str, values: &[(&str, &str)]) -> String {
+    values.iter().fold(template.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+// Fill a template with optional segments: text around a name inside the
+// braces only shows when the value isn't empty, so `{(scope)}` gives `(api)`
+// or nothing. A segment naming several values needs all of them, so
+// `{(scope)!}` only shows for a breaking commit with a scope. Braces without
+// a known name are kept as they are
+pub fn render_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        match render_segment(&rest[start + 1..end], values) {
+            Some(segment) => rendered.push_str(&segment),
+            None => rendered.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+// The text of one segment with every value it names filled in, empty when
+// one of them is empty. None when it names no value at all
+fn render_segment(segment: &str, values: &[(&str, &str)]) -> Option<String> {
+    let mut rendered = String::new();
+    let mut named = Vec::new();
+    let mut rest = segment;
+    while let Some(c) = rest.chars().next() {
+        // The longest name wins, and values are never searched again
+        match values.iter().filter(|(name, _)| !name.is_empty() && rest.starts_with(name)).max_by_key(|(name, _)| name.len()) {
+            Some((name, value)) => {
+                rendered.push_str(value);
+                named.push(*value);
+                rest = &rest[name.len()..];
+            }
+            None => {
+                rendered.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    match named.is_empty() {
+        true => None,
+        false if named.iter().any(|value| value.is_empty()) => Some(String::new()),
+        false => Some(rendered),
+    }
+}
+
+// Drop git-style comment lines and surrounding blank lines
+pub fn strip_comments(message: &str) -> String {
+    message
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+// Trim trailing whitespace on every line, separate paragraphs by exactly one
+// blank line and end the message with a single newline
+pub fn normalize_message(message: &str) -> String {
+    let mut normalized = String::new();
+    let mut pending_blank = false;
+    for line in message.lines().map(str::trim_end) {
+        if line.is_empty() {
+            pending_blank = !normalized.is_empty();
+            continue;
+        }
+        if pending_blank {
+            normalized.push('\n');
+            pending_blank = false;
+        }
+        normalized.push_str(line);
+        normalized.push('\n');
+    }
+    normalized
+}
+
+// Well-known trailer keys in the order they are rendered. Other keys, such as
+// the ticket footer, go right after BREAKING CHANGE in the order they were added
+const TRAILER_ORDER: &[&str] = &["BREAKING CHANGE", "", "Closes", "Co-authored-by", "Signed-off-by"];
+
+// The trailer block at the end of a commit message
+#[derive(Debug, Default)]
+pub struct Trailers {
+    entries: Vec<(String, String)>,
+}
+
+impl Trailers {
+    // Add a trailer, ignoring exact duplicates and empty values
+    pub fn add(&mut self, key: &str, value: &str) {
+        let value = value.trim();
+        if value.is_empty() || self.entries.iter().any(|(k, v)| k == key && v == value) {
+            return;
+        }
+        self.entries.push((key.to_string(), value.to_string()));
+    }
+
+    // Add the trailers from the last paragraph of an existing message, if
+    // every line of it is a trailer. Well-known keys get their usual spelling
+    pub fn add_from(&mut self, message: &str) {
+        let message = message.trim();
+        let Some((_, block)) = message.rsplit_once("\n\n") else {
+            return;
+        };
+        let parsed: Option<Vec<(&str, &str)>> = block
+            .lines()
+            .map(|line| {
+                let (key, value) = line.split_once(": ")?;
+                let valid = key == "BREAKING CHANGE" || (!key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+                valid.then_some((key, value))
+            })
+            .collect();
+        for (key, value) in parsed.into_iter().flatten() {
+            let key = TRAILER_ORDER.iter().find(|k| !k.is_empty() && k.eq_ignore_ascii_case(key)).copied().unwrap_or(key);
+            self.add(key, value);
+        }
+    }
+
+    // Append the trailers as one contiguous block, separated from the rest of
+    // the message by a single blank line
+    pub fn append_to(&self, message: &str) -> String {
+        if self.entries.is_empty() {
+            return message.to_string();
+        }
+        let rank = |key: &str| {
+            // Both spellings of the breaking change footer go first
+            let key = if key == "BREAKING-CHANGE" { "BREAKING CHANGE" } else { key };
+            TRAILER_ORDER
+                .iter()
+                .position(|k| *k == key)
+                .or_else(|| TRAILER_ORDER.iter().position(|k| k.is_empty()))
+                .unwrap_or_default()
+        };
+        let mut entries: Vec<&(String, String)> = self.entries.iter().collect();
+        entries.sort_by_key(|(key, _)| rank(key));
+        let block = entries
+            .iter()
+            .map(|(key, value)| format!("{}: {}", key, value))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!("{}\n\n{}", message.trim_end(), block)
+    }
+}
+
+// 1-based numbers of the body lines longer than `width`, skipping comments
+pub fn long_body_lines(message: &str, width: usize) -> Vec<usize> {
+    message
+        .lines()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, line)| !line.starts_with('#') && line.chars().count() > width)
+        .map(|(index, _)| index + 1)
+        .collect()
+}
+
+// Reflow the paragraphs of a body to `width`. Lists, indented blocks and
+// trailers are left alone since rewrapping would break them
+pub fn wrap_body(body: &str, width: usize) -> String {
+    body.split("\n\n")
+        .map(|paragraph| {
+            let structured = paragraph.lines().any(|line| {
+                let trimmed = line.trim_start();
+                line.starts_with([' ', '\t'])
+                    || trimmed.starts_with("- ")
+                    || trimmed.starts_with("* ")
+                    || trimmed.split_once(": ").is_some_and(|(key, _)| !key.is_empty() && !key.contains(' '))
+            });
+            if structured {
+                return paragraph.to_string();
+            }
+            let mut lines: Vec<String> = Vec::new();
+            for word in paragraph.split_whitespace() {
+                match lines.last_mut() {
+                    Some(line) if line.chars().count() + 1 + word.chars().count() <= width => {
+                        line.push(' ');
+                        line.push_str(word);
+                    }
+                    _ => lines.push(word.to_string()),
+                }
+            }
+            lines.join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "{type}{(scope)}{!}: {subject}";
+
+    fn header(scope: &str, breaking: &str) -> String {
+        render_template(HEADER, &[("type", "feat"), ("scope", scope), ("subject", "add x"), ("!", breaking)])
+    }
+
+    fn trailers_from(message: &str) -> Trailers {
+        let mut trailers = Trailers::default();
+        trailers.add_from(message);
+        trailers
+    }
+
+    #[test]
+    fn add_from_merges_with_new_trailers() {
+        let mut trailers = trailers_from("feat: x\n\nBody text.\n\nCloses: #1\nCo-authored-by: A <a@x.com>");
+        trailers.add("Closes", "#2");
+        trailers.add("Co-authored-by", "B <b@x.com>");
+        assert_eq!(
+            trailers.append_to("feat: y"),
+            "feat: y\n\nCloses: #1\nCloses: #2\nCo-authored-by: A <a@x.com>\nCo-authored-by: B <b@x.com>"
+        );
+    }
+
+    #[test]
+    fn add_from_drops_duplicates() {
+        let mut trailers = trailers_from("feat: x\n\nCloses: #1\nCloses: #1");
+        trailers.add("Closes", "#1");
+        assert_eq!(trailers.append_to("feat: y"), "feat: y\n\nCloses: #1");
+    }
+
+    #[test]
+    fn add_from_matches_well_known_keys_case_insensitively() {
+        let mut trailers = trailers_from("feat: x\n\nco-authored-BY: A <a@x.com>\nsigned-off-by: T <t@x.com>");
+        trailers.add("Co-authored-by", "A <a@x.com>");
+        assert_eq!(
+            trailers.append_to("feat: y"),
+            "feat: y\n\nCo-authored-by: A <a@x.com>\nSigned-off-by: T <t@x.com>"
+        );
+    }
+
+    #[test]
+    fn add_from_ignores_a_last_paragraph_that_is_not_trailers() {
+        assert_eq!(trailers_from("feat: x\n\nCloses: #1\nand some prose").append_to("feat: y"), "feat: y");
+        assert_eq!(trailers_from("feat: x\n\nSome note: with a space in the key").append_to("feat: y"), "feat: y");
+        // The header alone is never a trailer block
+        assert_eq!(trailers_from("Closes: #1").append_to("feat: y"), "feat: y");
+    }
+
+    #[test]
+    fn render_template_shows_scope_only_when_set() {
+        assert_eq!(header("api", ""), "feat(api): add x");
+        assert_eq!(header("", ""), "feat: add x");
+    }
+
+    #[test]
+    fn render_template_shows_breaking_marker_only_when_set() {
+        assert_eq!(header("api", "!"), "feat(api)!: add x");
+        assert_eq!(header("", "!"), "feat!: add x");
+    }
+
+    #[test]
+    fn render_template_keeps_unknown_braces() {
+        let rendered = render_template("{type}{(ticket}): {subject} {}", &[("type", "fix"), ("subject", "y")]);
+        assert_eq!(rendered, "fix{(ticket}): y {}");
+    }
+
+    #[test]
+    fn render_template_segment_with_two_values_needs_both() {
+        let render = |scope, breaking| render_template("{type}{(scope)!}: x", &[("type", "feat"), ("scope", scope), ("!", breaking)]);
+        assert_eq!(render("api", "!"), "feat(api)!: x");
+        assert_eq!(render("api", ""), "feat: x");
+        assert_eq!(render("", "!"), "feat: x");
+    }
+
+    #[test]
+    fn render_template_does_not_expand_names_inside_values() {
+        let rendered = render_template("{type}{(scope)}", &[("type", "feat"), ("scope", "type")]);
+        assert_eq!(rendered, "feat(type)");
+    }
+
+    #[test]
+    fn append_to_orders_trailers_by_kind() {
+        let mut trailers = Trailers::default();
+        trailers.add("Signed-off-by", "T <t@x.com>");
+        trailers.add("Co-authored-by", "A <a@x.com>");
+        trailers.add("Closes", "#1");
+        trailers.add("Reviewed-by", "R <r@x.com>");
+        trailers.add("BREAKING-CHANGE", "v1 is gone");
+        assert_eq!(
+            trailers.append_to("feat: x"),
+            "feat: x\n\nBREAKING-CHANGE: v1 is gone\nReviewed-by: R <r@x.com>\nCloses: #1\nCo-authored-by: A <a@x.com>\nSigned-off-by: T <t@x.com>"
+        );
+    }
+
+    #[test]
+    fn append_to_keeps_the_order_within_a_kind() {
+        let mut trailers = Trailers::default();
+        trailers.add("Closes", "#2");
+        trailers.add("BREAKING CHANGE", "a");
+        trailers.add("Closes", "#1");
+        assert_eq!(trailers.append_to("feat: x"), "feat: x\n\nBREAKING CHANGE: a\nCloses: #2\nCloses: #1");
+    }
+
+    #[test]
+    fn append_to_separates_with_one_blank_line() {
+        let mut trailers = Trailers::default();
+        trailers.add("Closes", "#1");
+        assert_eq!(trailers.append_to("feat: x\n\nBody.\n\n\n"), "feat: x\n\nBody.\n\nCloses: #1");
+        assert_eq!(trailers.append_to("feat: x  \n"), "feat: x\n\nCloses: #1");
+    }
+
+    #[test]
+    fn append_to_without_trailers_leaves_the_message_alone() {
+        assert_eq!(Trailers::default().append_to("feat: x\n"), "feat: x\n");
+    }
+
+    #[test]
+    fn normalize_message_trims_trailing_whitespace() {
+        assert_eq!(normalize_message("feat: x  \n\nBody\t \nmore "), "feat: x\n\nBody\nmore\n");
+    }
+
+    #[test]
+    fn normalize_message_collapses_blank_lines() {
+        assert_eq!(normalize_message("feat: x\n\n\n\nBody\n \n\t\nCloses: #1"), "feat: x\n\nBody\n\nCloses: #1\n");
+    }
+
+    #[test]
+    fn normalize_message_drops_leading_and_trailing_blank_lines() {
+        assert_eq!(normalize_message("\n  \nfeat: x\n\nBody\n\n\n"), "feat: x\n\nBody\n");
+        assert_eq!(normalize_message("\n \n"), "");
+    }
+
+    #[test]
+    fn normalize_message_converts_crlf() {
+        assert_eq!(normalize_message("feat: x\r\n\r\n\r\nBody \r\nmore\r\n"), "feat: x\n\nBody\nmore\n");
+    }
+}
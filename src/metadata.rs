@@ -0,0 +1,165 @@
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use slug::slugify;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::{get_gwf_dir, Config, MetadataLocation};
+use crate::error::IoContext;
+use crate::i18n::t;
+use crate::message::lossy_utf8;
+
+const GIT_METADATA_DIR: &str = "gwf";
+const REPO_METADATA_DIR: &str = ".gwf";
+
+// What nfb recorded for a branch. Files written by older versions only hold
+// the message, in which case type and scope come from the branch name.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct BranchMetadata {
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    pub message: String,
+    // Lines added with `gwf note`, committed as part of the body
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+// Directory where per-branch metadata files are stored
+pub fn metadata_dir(repo: &Repository, config: &Config) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let dir = match config.metadata_location.unwrap_or_default() {
+        MetadataLocation::Home => get_gwf_dir()?,
+        // Shared by all worktrees, since a branch can be finished from any of them
+        MetadataLocation::Git => repo.commondir().join(GIT_METADATA_DIR),
+        MetadataLocation::RepoLocal => {
+            let workdir = repo.workdir().ok_or("Could not get repository root")?;
+            workdir.join(REPO_METADATA_DIR)
+        }
+    };
+    resolve_dir(&dir)
+}
+
+// Make the directory absolute with symlinks resolved, so a symlinked or
+// relative HOME gives the same path whether or not the directory exists yet
+fn resolve_dir(dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let dir = if dir.is_absolute() {
+        dir.to_path_buf()
+    } else {
+        env::current_dir()?.join(dir)
+    };
+    let mut missing = Vec::new();
+    let mut existing = dir.as_path();
+    while !existing.exists() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return Ok(dir),
+        }
+    }
+    let mut resolved = existing.canonicalize().context("resolve gwf metadata directory", existing)?;
+    resolved.extend(missing.iter().rev());
+    Ok(resolved)
+}
+
+// Parts of a branch named type/scope/message or type/message
+#[derive(Debug)]
+pub struct BranchName<'a> {
+    pub type_: &'a str,
+    pub scope: &'a str,
+    pub message: &'a str,
+}
+
+pub fn parse_branch(branch: &str) -> Option<BranchName<'_>> {
+    let parts: Vec<&str> = branch.split('/').collect();
+    match parts.as_slice() {
+        [type_, message] => Some(BranchName { type_, scope: "", message }),
+        [type_, scope, message] => Some(BranchName { type_, scope, message }),
+        _ => None,
+    }
+}
+
+pub fn metadata_file(repo: &Repository, config: &Config, branch_name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(metadata_dir(repo, config)?.join(metadata_name(branch_name)?))
+}
+
+// File name for a branch's metadata. It must be a single path component so
+// the file can never land (or be removed) outside the metadata directory.
+pub fn metadata_name(branch_name: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let name = slugify(branch_name);
+    let mut components = Path::new(&name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(name),
+        _ => Err(t("invalid-metadata-name", &[("branch", &format!("{:?}", branch_name))]).into()),
+    }
+}
+
+// Whether a file name in the metadata directory is one metadata_name produces.
+// The home directory also holds gwf.toml, which never matches.
+pub fn is_metadata_name(name: &str) -> bool {
+    !name.is_empty() && slugify(name) == name
+}
+
+// Remove a metadata file, refusing anything that doesn't resolve to a direct
+// child of the metadata directory
+pub fn remove_metadata(repo: &Repository, config: &Config, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = metadata_dir(repo, config)?;
+    let dir = dir.canonicalize().context("resolve gwf metadata directory", &dir)?;
+    let resolved = path.canonicalize().context("resolve branch metadata", path)?;
+    if resolved.parent() != Some(dir.as_path()) || !resolved.is_file() {
+        let (path, dir) = (path.display().to_string(), dir.display().to_string());
+        return Err(t("not-a-metadata-file", &[("path", &path), ("dir", &dir)]).into());
+    }
+    fs::remove_file(&resolved).context("remove branch metadata", &resolved)?;
+    Ok(())
+}
+
+// Create the metadata directory, keeping a repo-local one out of `git status`
+pub fn prepare_metadata_dir(repo: &Repository, config: &Config) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let dir = metadata_dir(repo, config)?;
+    fs::create_dir_all(&dir).context("create gwf metadata directory", &dir)?;
+
+    if config.metadata_location == Some(MetadataLocation::RepoLocal) {
+        // Git only reads info/exclude from the common dir, even in a linked worktree
+        let exclude_file = repo.commondir().join("info").join("exclude");
+        let pattern = format!("/{}/", REPO_METADATA_DIR);
+        let excludes = fs::read_to_string(&exclude_file).unwrap_or_default();
+        if !excludes.lines().any(|line| line.trim() == pattern) {
+            if let Some(info_dir) = exclude_file.parent() {
+                fs::create_dir_all(info_dir).context("create git info directory", info_dir)?;
+            }
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&exclude_file)
+                .context("open git exclude file", &exclude_file)?;
+            let separator = if !excludes.is_empty() && !excludes.ends_with('\n') { "\n" } else { "" };
+            writeln!(file, "{}{}", separator, pattern).context("update git exclude file", &exclude_file)?;
+        }
+    }
+
+    Ok(dir)
+}
+
+pub fn write_metadata(path: &Path, metadata: &BranchMetadata) -> Result<(), Box<dyn std::error::Error>> {
+    fs::write(path, toml::to_string(metadata)?).context("write branch metadata", path)?;
+    Ok(())
+}
+
+// Read a branch's metadata, None if the file doesn't exist
+pub fn read_metadata(path: &Path) -> Result<Option<BranchMetadata>, Box<dyn std::error::Error>> {
+    let content = match fs::read(path) {
+        Ok(bytes) => lossy_utf8(bytes, &path.display().to_string()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).context("read branch metadata", path),
+    };
+    let metadata = toml::from_str(&content).unwrap_or_else(|_| BranchMetadata {
+        message: content.trim().to_string(),
+        ..Default::default()
+    });
+    Ok(Some(metadata))
+}
@@ -0,0 +1,25 @@
+use dialoguer::Confirm;
+use std::io::{self, IsTerminal};
+
+use crate::i18n::t;
+
+// Prompts are only shown on a terminal, and never with --yes
+pub fn can_prompt(assume_yes: bool) -> bool {
+    !assume_yes && io::stdin().is_terminal()
+}
+
+// Error for a required value that can't be prompted for
+pub fn missing(input: &str, flag: &str) -> Box<dyn std::error::Error> {
+    t("missing-input", &[("input", input), ("flag", flag)]).into()
+}
+
+// Ask a yes/no question. --yes answers yes, without a terminal the default is taken
+pub fn confirm(prompt: &str, default: bool, assume_yes: bool) -> Result<bool, Box<dyn std::error::Error>> {
+    if assume_yes {
+        return Ok(true);
+    }
+    if !io::stdin().is_terminal() {
+        return Ok(default);
+    }
+    Ok(Confirm::new().with_prompt(prompt).default(default).interact()?)
+}
@@ -0,0 +1,127 @@
+use git2::{BranchType, Repository, StatusOptions};
+use slug::slugify;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::conventional::parse_conventional;
+use crate::error::IoContext;
+use crate::history::commits;
+use crate::metadata::parse_branch;
+
+// How many commits back to look for scopes already in use
+const SCOPE_HISTORY_LIMIT: usize = 500;
+
+// Infer a scope from the paths touched in the working tree.
+// If every change lives inside one package (a directory with a Cargo.toml or
+// package.json) the package name is used, otherwise the common directory.
+pub fn infer_scope(repo: &Repository) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let workdir = match repo.workdir() {
+        Some(workdir) => workdir,
+        None => return Ok(None),
+    };
+
+    let mut status_opts = StatusOptions::new();
+    status_opts.include_untracked(true).recurse_untracked_dirs(true);
+    let statuses = repo.statuses(Some(&mut status_opts))?;
+    let paths: Vec<PathBuf> = statuses
+        .iter()
+        .filter_map(|entry| entry.path().map(PathBuf::from))
+        .collect();
+
+    let common = match common_dir(&paths) {
+        Some(common) if common.components().next().is_some() => common,
+        _ => return Ok(None),
+    };
+
+    // Look for the closest enclosing package below the repository root
+    let mut dir = Some(common.as_path());
+    while let Some(current) = dir {
+        if current.as_os_str().is_empty() {
+            break;
+        }
+        if let Some(name) = package_name(&workdir.join(current)) {
+            return Ok(Some(name));
+        }
+        dir = current.parent();
+    }
+
+    Ok(Some(slugify(common.to_string_lossy())))
+}
+
+// The scope for the directory gwf runs in: its name, slugified, so running in
+// packages/api gives `api`. None at the repository root or outside it
+pub fn scope_from_cwd(repo: &Repository) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let Some(workdir) = repo.workdir() else {
+        return Ok(None);
+    };
+    let workdir = workdir.canonicalize().context("resolve repository root", workdir)?;
+    let cwd = env::current_dir()?;
+    let cwd = cwd.canonicalize().context("resolve current directory", &cwd)?;
+    let Ok(relative) = cwd.strip_prefix(&workdir) else {
+        return Ok(None);
+    };
+    Ok(relative.file_name().map(|name| slugify(name.to_string_lossy())).filter(|scope| !scope.is_empty()))
+}
+
+// Longest directory shared by all paths
+fn common_dir(paths: &[PathBuf]) -> Option<PathBuf> {
+    let mut iter = paths.iter();
+    let mut common = iter.next()?.parent()?.to_path_buf();
+    for path in iter {
+        while !path.starts_with(&common) {
+            common = common.parent()?.to_path_buf();
+        }
+    }
+    Some(common)
+}
+
+// Name declared by a Cargo.toml or package.json in `dir`
+fn package_name(dir: &Path) -> Option<String> {
+    if let Ok(content) = fs::read_to_string(dir.join("Cargo.toml")) {
+        let manifest: toml::Value = toml::from_str(&content).ok()?;
+        if let Some(name) = manifest.get("package").and_then(|p| p.get("name")).and_then(|n| n.as_str()) {
+            return Some(name.to_string());
+        }
+    }
+    if let Ok(content) = fs::read_to_string(dir.join("package.json")) {
+        let manifest: serde_json::Value = serde_json::from_str(&content).ok()?;
+        if let Some(name) = manifest.get("name").and_then(|n| n.as_str()) {
+            return Some(name.to_string());
+        }
+    }
+    None
+}
+
+// The spelling of `scope` already used in recent commits or branch names when
+// it differs only in case, picking the most common one
+pub fn canonical_scope(repo: &Repository, scope: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut count = |known: &str| {
+        if known.eq_ignore_ascii_case(scope) {
+            *counts.entry(known.to_string()).or_default() += 1;
+        }
+    };
+
+    if let Ok(head) = repo.head().and_then(|head| head.peel_to_commit()) {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(head.id())?;
+        for commit in commits(repo, revwalk).take(SCOPE_HISTORY_LIMIT) {
+            let commit = commit?;
+            let summary = String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()).into_owned();
+            if let Some(known) = parse_conventional(&summary).ok().and_then(|c| c.scope) {
+                count(known);
+            }
+        }
+    }
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        if let Some(parsed) = branch.name()?.and_then(parse_branch) {
+            count(parsed.scope);
+        }
+    }
+
+    let canonical = counts.into_iter().max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0))).map(|(known, _)| known);
+    Ok(canonical.filter(|known| known != scope))
+}
@@ -0,0 +1,59 @@
+use git2::{Repository, Signature};
+use std::env;
+
+use crate::date::parse_date;
+use crate::i18n::t;
+
+// Which side of a commit a signature is for, naming its GIT_* variables
+#[derive(Debug, Clone, Copy)]
+pub enum Role {
+    Author,
+    Committer,
+}
+
+impl Role {
+    fn var(self, field: &str) -> String {
+        let role = match self {
+            Role::Author => "AUTHOR",
+            Role::Committer => "COMMITTER",
+        };
+        format!("GIT_{}_{}", role, field)
+    }
+}
+
+// The signature git would use: GIT_AUTHOR_NAME/EMAIL/DATE (or the COMMITTER
+// ones) when set, the user.name/user.email config and the current time otherwise.
+// Without any variable the config signature is used as is, so its bytes are
+// kept even when they aren't UTF-8
+pub fn signature(repo: &Repository, role: Role) -> Result<Signature<'static>, Box<dyn std::error::Error>> {
+    let var = |field: &str| -> Result<Option<String>, String> {
+        match env::var(role.var(field)) {
+            Ok(value) => Ok(Some(value).filter(|value| !value.is_empty())),
+            Err(env::VarError::NotPresent) => Ok(None),
+            Err(env::VarError::NotUnicode(_)) => Err(t("not-utf8", &[("field", &role.var(field))])),
+        }
+    };
+    let (name, email, date) = (var("NAME")?, var("EMAIL")?, var("DATE")?);
+    if name.is_none() && email.is_none() && date.is_none() {
+        return Ok(repo.signature()?);
+    }
+    let config = match (&name, &email) {
+        (Some(_), Some(_)) => None,
+        _ => Some(repo.signature()?),
+    };
+    let name = match name {
+        Some(name) => name,
+        None => config.as_ref().and_then(|sig| sig.name()).ok_or_else(|| t("not-utf8", &[("field", "user.name")]))?.to_string(),
+    };
+    let email = match email {
+        Some(email) => email,
+        None => config.as_ref().and_then(|sig| sig.email()).ok_or_else(|| t("not-utf8", &[("field", "user.email")]))?.to_string(),
+    };
+    match date {
+        Some(date) => {
+            let time = parse_date(&date).map_err(|e| t("invalid-env-date", &[("var", &role.var("DATE")), ("error", &e.to_string())]))?;
+            Ok(Signature::new(&name, &email, &time)?)
+        }
+        None => Ok(Signature::now(&name, &email)?),
+    }
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use common::{check, TestRepo};
+use git2::{Signature, Time};
+
+// Commit `path` on HEAD at the given UTC time
+fn commit_at(test: &TestRepo, path: &str, message: &str, seconds: i64) {
+    test.change(path, path);
+    let sig = Signature::new("Test", "test@example.com", &Time::new(seconds, 0)).unwrap();
+    let tree = test.repo.find_tree(test.repo.index().unwrap().write_tree().unwrap()).unwrap();
+    let parent = test.head();
+    test.repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &[&parent]).unwrap();
+}
+
+#[test]
+fn until_includes_commits_made_that_day() {
+    let test = TestRepo::new();
+    commit_at(&test, "a", "feat: before the range", 1703980800); // 2023-12-31T00:00:00Z
+    commit_at(&test, "b", "feat: on the first day", 1704067200); // 2024-01-01T00:00:00Z
+    commit_at(&test, "c", "fix: on the last day", 1709287200); // 2024-03-01T10:00:00Z
+    commit_at(&test, "d", "fix: after the range", 1709337600); // 2024-03-02T00:00:00Z
+
+    let output = check(test.command(&["changelog", "--since", "2024-01-01", "--until", "2024-03-01"]).env("TZ", "UTC").output().unwrap());
+    assert!(output.contains("on the first day"), "{}", output);
+    assert!(output.contains("on the last day"), "{}", output);
+    assert!(!output.contains("before the range"), "{}", output);
+    assert!(!output.contains("after the range"), "{}", output);
+}
+
+#[test]
+fn until_a_time_is_inclusive() {
+    let test = TestRepo::new();
+    commit_at(&test, "c", "fix: at the bound", 1709287200); // 2024-03-01T10:00:00Z
+    commit_at(&test, "d", "fix: a second later", 1709287201);
+
+    let output = check(test.command(&["changelog", "--until", "2024-03-01T10:00:00Z"]).output().unwrap());
+    assert!(output.contains("at the bound"), "{}", output);
+    assert!(!output.contains("a second later"), "{}", output);
+}
@@ -0,0 +1,131 @@
+// Helpers for running the gwf binary against throwaway repositories
+#![allow(dead_code)]
+
+use git2::{Commit, Repository, Signature};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+// Variables that would make a test depend on the environment it runs in
+const CLEARED_VARS: &[&str] = &[
+    "GIT_DIR",
+    "GIT_WORK_TREE",
+    "GIT_INDEX_FILE",
+    "GIT_AUTHOR_NAME",
+    "GIT_AUTHOR_EMAIL",
+    "GIT_AUTHOR_DATE",
+    "GIT_COMMITTER_NAME",
+    "GIT_COMMITTER_EMAIL",
+    "GIT_COMMITTER_DATE",
+    "GIT_EDITOR",
+    "GWF_DIR",
+    "GWF_PROFILE",
+    "CLICOLOR_FORCE",
+];
+
+// A repository with its own HOME, so ~/.gwf and the global git config are empty
+pub struct TestRepo {
+    pub home: TempDir,
+    pub dir: TempDir,
+    pub repo: Repository,
+}
+
+impl TestRepo {
+    // A repository on `main` with one commit
+    pub fn new() -> TestRepo {
+        let test = TestRepo::empty();
+        test.write("README", "readme\n");
+        test.stage("README");
+        let sig = Signature::now("Test", "test@example.com").unwrap();
+        {
+            let tree = test.repo.find_tree(test.repo.index().unwrap().write_tree().unwrap()).unwrap();
+            test.repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[]).unwrap();
+        }
+        test
+    }
+
+    // A repository on `main` without commits
+    pub fn empty() -> TestRepo {
+        let home = TempDir::new().unwrap();
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        repo.set_head("refs/heads/main").unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        TestRepo { home, dir, repo }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn write(&self, path: &str, content: &str) {
+        let path = self.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    pub fn stage(&self, path: &str) {
+        let mut index = self.repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+    }
+
+    // Write and stage a file in one go
+    pub fn change(&self, path: &str, content: &str) {
+        self.write(path, content);
+        self.stage(path);
+    }
+
+    // gwf run inside the repository, with -y so nothing prompts
+    pub fn command(&self, args: &[&str]) -> Command {
+        self.command_in(self.path(), args)
+    }
+
+    pub fn command_in(&self, dir: &Path, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_gwf"));
+        command.arg("-y").args(args).current_dir(dir).env("HOME", self.home.path()).env("GWF_LANG", "en").env("NO_COLOR", "1");
+        for var in CLEARED_VARS {
+            command.env_remove(var);
+        }
+        command
+    }
+
+    // Run gwf and panic with its output unless it succeeds
+    pub fn gwf(&self, args: &[&str]) -> String {
+        check(self.command(args).output().unwrap())
+    }
+
+    // Run gwf expecting it to fail, returning stderr
+    pub fn gwf_fails(&self, args: &[&str]) -> String {
+        let output = self.command(args).output().unwrap();
+        assert!(!output.status.success(), "gwf {:?} succeeded:\n{}", args, String::from_utf8_lossy(&output.stdout));
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    }
+
+    pub fn head(&self) -> Commit<'_> {
+        self.repo.head().unwrap().peel_to_commit().unwrap()
+    }
+
+    pub fn head_branch(&self) -> String {
+        self.repo.head().unwrap().shorthand().unwrap().to_string()
+    }
+
+    // Where metadata lives by default
+    pub fn gwf_dir(&self) -> PathBuf {
+        self.home.path().join(".gwf")
+    }
+}
+
+// stdout of a successful run
+pub fn check(output: Output) -> String {
+    assert!(
+        output.status.success(),
+        "gwf failed:\nstdout: {}\nstderr: {}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
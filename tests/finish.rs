@@ -0,0 +1,157 @@
+mod common;
+
+use common::{check, TestRepo};
+use git2::BranchType;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+// Override user.name in the repository config with raw bytes
+fn set_raw_user_name(test: &TestRepo, name: &[u8]) {
+    let mut config = OpenOptions::new().append(true).open(test.repo.path().join("config")).unwrap();
+    config.write_all(b"[user]\n\tname = ").unwrap();
+    config.write_all(name).unwrap();
+    config.write_all(b"\n").unwrap();
+}
+
+#[test]
+fn non_ascii_author_round_trips() {
+    let test = TestRepo::new();
+    test.repo.config().unwrap().set_str("user.name", "José Müller Ñandú").unwrap();
+    test.gwf(&["nfb", "-t", "feat", "-m", "add x"]);
+    test.change("x", "x\n");
+    test.gwf(&["finish", "--signoff", "--date", "2024-01-02T03:04:05Z"]);
+
+    let head = test.head();
+    assert_eq!(head.author().name(), Some("José Müller Ñandú"));
+    assert_eq!(head.committer().name(), Some("José Müller Ñandú"));
+    assert_eq!(head.author().when().seconds(), 1704164645);
+    assert!(head.message().unwrap().contains("Signed-off-by: José Müller Ñandú <test@example.com>"));
+}
+
+#[test]
+fn non_utf8_author_keeps_its_bytes() {
+    let test = TestRepo::new();
+    set_raw_user_name(&test, b"Jos\xe9");
+    test.gwf(&["nfb", "-t", "feat", "-m", "add x"]);
+    test.change("x", "x\n");
+    test.gwf(&["finish"]);
+
+    let head = test.head();
+    assert_eq!(head.author().name_bytes(), b"Jos\xe9");
+    assert_eq!(head.committer().name_bytes(), b"Jos\xe9");
+}
+
+#[test]
+fn non_utf8_author_cannot_be_dated() {
+    let test = TestRepo::new();
+    set_raw_user_name(&test, b"Jos\xe9");
+    test.gwf(&["nfb", "-t", "feat", "-m", "add x"]);
+    test.change("x", "x\n");
+    let stderr = test.gwf_fails(&["finish", "--date", "2024-01-02T03:04:05Z"]);
+    assert!(stderr.contains("user.name is not valid UTF-8, so its date can't be changed"), "{}", stderr);
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_author_can_be_signed() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let test = TestRepo::new();
+    set_raw_user_name(&test, b"Jos\xe9");
+    let program = test.home.path().join("fake-gpg");
+    std::fs::write(&program, "#!/bin/sh\ncat > /dev/null\nprintf -- '-----BEGIN PGP SIGNATURE-----\\n\\nabc\\n-----END PGP SIGNATURE-----\\n'\n").unwrap();
+    std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let mut config = test.repo.config().unwrap();
+    config.set_bool("commit.gpgsign", true).unwrap();
+    config.set_str("gpg.program", program.to_str().unwrap()).unwrap();
+
+    test.gwf(&["nfb", "-t", "feat", "-m", "add x"]);
+    test.change("x", "x\n");
+    test.gwf(&["finish"]);
+
+    let head = test.head();
+    assert_eq!(head.author().name_bytes(), b"Jos\xe9");
+    let (signature, signed) = test.repo.extract_signature(&head.id(), None).unwrap();
+    assert!(signature.as_str().unwrap().contains("abc"));
+    assert!(signed.windows(4).any(|window| window == b"Jos\xe9"));
+}
+
+#[test]
+fn first_commit_on_an_unborn_branch() {
+    let test = TestRepo::empty();
+    test.change("README", "readme\n");
+    test.gwf(&["finish"]);
+
+    let head = test.head();
+    assert_eq!(head.summary(), Some("chore: initial commit"));
+    assert_eq!(head.parent_count(), 0);
+    assert_eq!(test.head_branch(), "main");
+    assert!(head.tree().unwrap().get_name("README").is_some());
+}
+
+#[test]
+fn first_commit_keeps_an_explicit_message() {
+    let test = TestRepo::empty();
+    test.change("README", "readme\n");
+    test.gwf(&["finish", "-t", "docs", "-m", "add readme"]);
+
+    let head = test.head();
+    assert_eq!(head.summary(), Some("docs: add readme"));
+    assert_eq!(head.parent_count(), 0);
+}
+
+#[test]
+fn amend_author_keeps_message_and_tree() {
+    let test = TestRepo::new();
+    let before = test.head();
+    let output = test.gwf(&["finish", "--amend-author", "Ana <ana@example.com>"]);
+    assert!(output.starts_with(&format!("Changed the author of commit {} to Ana <ana@example.com>: ", before.id())), "{}", output);
+
+    let head = test.head();
+    assert_eq!((head.author().name(), head.author().email()), (Some("Ana"), Some("ana@example.com")));
+    assert_eq!((head.message(), head.tree_id()), (before.message(), before.tree_id()));
+}
+
+#[test]
+fn amend_author_dry_run_is_translated() {
+    let test = TestRepo::new();
+    let output = check(test.command(&["finish", "-n", "--amend-author", "Ana <ana@example.com>"]).env("GWF_LANG", "es").output().unwrap());
+    assert_eq!(output, format!("Se cambiaría el autor del commit {} a Ana <ana@example.com>\n", test.head().id()));
+}
+
+#[test]
+fn onto_commits_on_a_new_branch() {
+    let test = TestRepo::new();
+    let base = test.head().id();
+    test.change("x", "x\n");
+    test.gwf(&["finish", "--onto", "feat/x", "-t", "feat", "-m", "add x"]);
+
+    assert_eq!(test.head_branch(), "feat/x");
+    assert_eq!(test.head().summary(), Some("feat: add x"));
+    assert_eq!(test.head().parent_id(0).unwrap(), base);
+    let main = test.repo.find_branch("main", BranchType::Local).unwrap();
+    assert_eq!(main.get().target(), Some(base));
+}
+
+#[test]
+fn failed_onto_leaves_head_alone() {
+    let test = TestRepo::new();
+    let stderr = test.gwf_fails(&["finish", "--onto", "feat/oops", "-t", "feat", "-m", "x"]);
+    assert!(stderr.contains("Nothing to commit"), "{}", stderr);
+    assert_eq!(test.head_branch(), "main");
+    assert!(test.repo.find_branch("feat/oops", BranchType::Local).is_err());
+
+    // A check that fails with changes staged doesn't create it either
+    test.change("x", "x\n");
+    fs::create_dir_all(test.gwf_dir()).unwrap();
+    fs::write(test.gwf_dir().join("gwf.toml"), "expected_author_domain = \"corp.example\"\n").unwrap();
+    let stderr = test.gwf_fails(&["finish", "--onto", "feat/oops", "-t", "feat", "-m", "x"]);
+    assert!(stderr.contains("is not in the expected domain"), "{}", stderr);
+    assert_eq!(test.head_branch(), "main");
+    assert!(test.repo.find_branch("feat/oops", BranchType::Local).is_err());
+
+    let stderr = test.gwf_fails(&["finish", "--onto", "main", "-t", "feat", "-m", "x", "--force"]);
+    assert!(stderr.contains("Branch main already exists"), "{}", stderr);
+    assert_eq!(test.head_branch(), "main");
+    assert!(test.repo.find_branch("feat/oops", BranchType::Local).is_err());
+}
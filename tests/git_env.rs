@@ -0,0 +1,32 @@
+mod common;
+
+use common::{check, TestRepo};
+use tempfile::TempDir;
+
+#[test]
+fn git_dir_and_work_tree_pick_the_repository() {
+    let test = TestRepo::new();
+    // Somewhere that is not a repository, as in a hook run by other tooling
+    let elsewhere = TempDir::new().unwrap();
+    let git_dir = test.repo.path();
+    let run = |args: &[&str]| {
+        check(test.command_in(elsewhere.path(), args).env("GIT_DIR", git_dir).env("GIT_WORK_TREE", test.path()).output().unwrap())
+    };
+
+    run(&["nfb", "-t", "feat", "-m", "add x"]);
+    assert_eq!(test.head_branch(), "feat/add-x");
+    test.change("x", "x\n");
+    run(&["finish"]);
+    assert_eq!(test.head().summary(), Some("feat: add x"));
+}
+
+#[test]
+fn git_dir_wins_over_the_current_directory() {
+    let test = TestRepo::new();
+    let other = TestRepo::new();
+    let args = ["nfb", "-t", "feat", "-m", "add x"];
+    check(other.command(&args).env("GIT_DIR", test.repo.path()).env("GIT_WORK_TREE", test.path()).output().unwrap());
+
+    assert_eq!(test.head_branch(), "feat/add-x");
+    assert_eq!(other.head_branch(), "main");
+}
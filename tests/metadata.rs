@@ -0,0 +1,33 @@
+mod common;
+
+use common::{check, TestRepo};
+use std::fs;
+use tempfile::TempDir;
+
+#[cfg(unix)]
+#[test]
+fn symlinked_home_round_trips_a_branch() {
+    let test = TestRepo::new();
+    let links = TempDir::new().unwrap();
+    let home = links.path().join("home");
+    std::os::unix::fs::symlink(test.home.path(), &home).unwrap();
+
+    check(test.command(&["nfb", "-t", "feat", "-m", "add x", "-m", "Stored body."]).env("HOME", &home).output().unwrap());
+    let stored: Vec<_> = fs::read_dir(test.gwf_dir()).unwrap().collect();
+    assert_eq!(stored.len(), 1, "metadata should be written through the symlink");
+
+    test.change("x", "x\n");
+    check(test.command(&["finish"]).env("HOME", &home).output().unwrap());
+    assert_eq!(test.head().message(), Some("feat: add x\n\nStored body.\n"));
+}
+
+#[test]
+fn huge_max_age_never_expires() {
+    let test = TestRepo::new();
+    fs::create_dir_all(test.gwf_dir()).unwrap();
+    fs::write(test.gwf_dir().join("gwf.toml"), format!("metadata_max_age_days = {}\n", i64::MAX)).unwrap();
+    test.gwf(&["nfb", "-t", "feat", "-m", "add x"]);
+    let output = test.gwf(&["prune"]);
+    assert!(output.contains("Removed 0 metadata file(s)"), "{}", output);
+    assert!(test.gwf_dir().join("feat-add-x").exists());
+}
@@ -0,0 +1,23 @@
+mod common;
+
+use common::TestRepo;
+use git2::BranchType;
+
+#[test]
+fn unknown_type_is_rejected_with_and_without_dry_run() {
+    let test = TestRepo::new();
+    for args in [&["nfb", "-t", "bogus", "-m", "x y", "--dry-run"][..], &["nfb", "-t", "bogus", "-m", "x y"]] {
+        let stderr = test.gwf_fails(args);
+        assert!(stderr.contains("Type `bogus` must be one of"), "{}", stderr);
+    }
+    assert!(test.repo.find_branch("bogus/x-y", BranchType::Local).is_err());
+    assert_eq!(test.head_branch(), "main");
+}
+
+#[test]
+fn dry_run_reports_a_resumed_branch() {
+    let test = TestRepo::new();
+    test.gwf(&["nfb", "-t", "feat", "-m", "add x", "--switch-back-after"]);
+    let output = test.gwf(&["nfb", "-t", "feat", "-m", "add x", "--resume", "--dry-run", "--no-metadata"]);
+    assert_eq!(output, "Would resume existing branch: feat/add-x\n");
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::{check, TestRepo};
+
+// Finish a feature branch with extra environment variables set
+fn finish_with(test: &TestRepo, vars: &[(&str, &str)]) {
+    test.gwf(&["nfb", "-t", "feat", "-m", "add x"]);
+    test.change("x", "x\n");
+    check(test.command(&["finish"]).envs(vars.iter().copied()).output().unwrap());
+}
+
+#[test]
+fn config_is_used_without_variables() {
+    let test = TestRepo::new();
+    finish_with(&test, &[]);
+    let head = test.head();
+    assert_eq!((head.author().name(), head.author().email()), (Some("Test"), Some("test@example.com")));
+}
+
+#[test]
+fn name_alone_keeps_the_config_email() {
+    let test = TestRepo::new();
+    finish_with(&test, &[("GIT_AUTHOR_NAME", "Ana")]);
+    let head = test.head();
+    assert_eq!((head.author().name(), head.author().email()), (Some("Ana"), Some("test@example.com")));
+    assert_eq!(head.committer().name(), Some("Test"));
+}
+
+#[test]
+fn email_alone_keeps_the_config_name() {
+    let test = TestRepo::new();
+    finish_with(&test, &[("GIT_COMMITTER_EMAIL", "ci@example.com")]);
+    let head = test.head();
+    assert_eq!((head.committer().name(), head.committer().email()), (Some("Test"), Some("ci@example.com")));
+    assert_eq!(head.author().email(), Some("test@example.com"));
+}
+
+#[test]
+fn date_alone_keeps_the_config_identity() {
+    let test = TestRepo::new();
+    finish_with(&test, &[("GIT_AUTHOR_DATE", "2024-01-02T03:04:05Z")]);
+    let head = test.head();
+    assert_eq!((head.author().name(), head.author().email()), (Some("Test"), Some("test@example.com")));
+    assert_eq!(head.author().when().seconds(), 1704164645);
+    assert_ne!(head.committer().when().seconds(), 1704164645);
+}
+
+#[test]
+fn all_variables_together() {
+    let test = TestRepo::new();
+    finish_with(
+        &test,
+        &[
+            ("GIT_AUTHOR_NAME", "Ana"),
+            ("GIT_AUTHOR_EMAIL", "ana@example.com"),
+            ("GIT_AUTHOR_DATE", "@1704164645 +0100"),
+            ("GIT_COMMITTER_NAME", "Bot"),
+            ("GIT_COMMITTER_EMAIL", "bot@example.com"),
+            ("GIT_COMMITTER_DATE", "2024-01-03T00:00:00Z"),
+        ],
+    );
+    let head = test.head();
+    let (author, committer) = (head.author(), head.committer());
+    assert_eq!((author.name(), author.email()), (Some("Ana"), Some("ana@example.com")));
+    assert_eq!((author.when().seconds(), author.when().offset_minutes()), (1704164645, 60));
+    assert_eq!((committer.name(), committer.email()), (Some("Bot"), Some("bot@example.com")));
+    assert_eq!(committer.when().seconds(), 1704240000);
+}
+
+#[test]
+fn invalid_date_is_an_error() {
+    let test = TestRepo::new();
+    test.gwf(&["nfb", "-t", "feat", "-m", "add x"]);
+    test.change("x", "x\n");
+    let output = test.command(&["finish"]).env("GIT_AUTHOR_DATE", "next tuesday").output().unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Invalid GIT_AUTHOR_DATE"), "{}", stderr);
+    assert_eq!(test.head().summary(), Some("init"));
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::{check, TestRepo};
+use git2::{BranchType, Repository};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+// A linked worktree of the test repository, checked out on a branch named `wt`
+fn add_worktree(test: &TestRepo) -> (TempDir, Repository) {
+    let parent = TempDir::new().unwrap();
+    let path = parent.path().join("wt");
+    let worktree = test.repo.worktree("wt", &path, None).unwrap();
+    let repo = Repository::open_from_worktree(&worktree).unwrap();
+    (parent, repo)
+}
+
+// Write and stage a file in the worktree
+fn change(worktree: &Repository, path: &str, content: &str) {
+    fs::write(worktree.workdir().unwrap().join(path), content).unwrap();
+    let mut index = worktree.index().unwrap();
+    index.add_path(Path::new(path)).unwrap();
+    index.write().unwrap();
+}
+
+#[test]
+fn nfb_and_finish_inside_a_linked_worktree() {
+    let test = TestRepo::new();
+    let (_parent, worktree) = add_worktree(&test);
+    let dir = worktree.workdir().unwrap();
+    check(test.command_in(dir, &["nfb", "-t", "feat", "-m", "add x", "-m", "Stored body."]).output().unwrap());
+    assert_eq!(worktree.head().unwrap().shorthand(), Some("feat/add-x"));
+    change(&worktree, "x", "x\n");
+    check(test.command_in(dir, &["finish"]).output().unwrap());
+
+    let head = worktree.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(head.message(), Some("feat: add x\n\nStored body.\n"));
+    // The main checkout is left alone
+    assert_eq!(test.head_branch(), "main");
+    assert_eq!(test.head().summary(), Some("init"));
+    let branch = test.repo.find_branch("feat/add-x", BranchType::Local).unwrap();
+    assert_eq!(branch.get().target(), Some(head.id()));
+}
+
+#[test]
+fn git_metadata_is_shared_by_worktrees() {
+    let test = TestRepo::new();
+    fs::create_dir_all(test.gwf_dir()).unwrap();
+    fs::write(test.gwf_dir().join("gwf.toml"), "metadata_location = \"git\"\n").unwrap();
+    let (_parent, worktree) = add_worktree(&test);
+
+    let dir = worktree.workdir().unwrap();
+    check(test.command_in(dir, &["nfb", "-t", "feat", "-m", "add x", "-m", "Stored body."]).output().unwrap());
+    // Stored in the common git dir, not the worktree's private one
+    assert_eq!(fs::read_dir(test.repo.path().join("gwf")).unwrap().count(), 1);
+
+    change(&worktree, "x", "x\n");
+    check(test.command_in(dir, &["finish"]).output().unwrap());
+    let head = worktree.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(head.message(), Some("feat: add x\n\nStored body.\n"));
+}